@@ -3,6 +3,7 @@
 use super::{cell::IntoCell, core::AllocatingBuilder};
 
 /// An array of cells.
+#[allow(dead_code)]
 pub struct CellArray<'a, const N: usize, const U: usize, T: IntoCell<'a, N>> {
     memory: &'a AllocatingBuilder<N>,
     data: [T; U],
@@ -21,77 +21,104 @@ impl<'a, const N: usize> CellBool<'a, N> {
     }
 
     /// Executes code while this cell is true.
+    #[track_caller]
     pub fn while_true(&self, f: impl FnOnce(&Self)) {
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.0.location);
-            builder.source_mut().push('[');
-        }
-
-        f(self);
-
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.0.location);
-            builder.source_mut().push(']');
-        }
+        traced!(self.0.memory, "CellBool::while_true", {
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.0.location);
+                builder.source_mut().push('[');
+            }
+
+            f(self);
+
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.0.location);
+                builder.source_mut().push(']');
+            }
+        });
     }
 
     /// Executes code while this cell is true.
+    #[track_caller]
     pub fn while_true_mut(&mut self, f: impl FnOnce(&mut Self)) {
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.0.location);
-            builder.source_mut().push('[');
-        }
-
-        f(self);
-
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.0.location);
-            builder.source_mut().push(']');
-        }
+        traced!(self.0.memory, "CellBool::while_true_mut", {
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.0.location);
+                builder.source_mut().push('[');
+            }
+
+            f(self);
+
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.0.location);
+                builder.source_mut().push(']');
+            }
+        });
     }
 
     /// Executes code if this cell is true.
+    #[track_caller]
     pub fn if_true(mut self, f: impl FnOnce()) {
-        self.while_true_mut(|this| {
-            f();
-            this.0.zero();
+        traced!(self.0.memory, "CellBool::if_true", {
+            self.while_true_mut(|this| {
+                f();
+                this.0.zero();
+            });
         });
     }
 
     /// Sets the value of this cell.
+    #[track_caller]
     pub fn set(&mut self, value: bool) {
-        self.0.set(value as u8);
+        traced!(self.0.memory, "CellBool::set", {
+            self.0.set(value as u8);
+        });
     }
 
     /// Negates the value contained in this cell.
+    #[track_caller]
     pub fn negate(&mut self) {
-        let mut temp = self.0.clone();
-        self.0.set(1);
-        self.0.sub_and_zero(&mut temp);
+        traced!(self.0.memory, "CellBool::negate", {
+            let mut temp = self.0.clone();
+            self.0.set(1);
+            self.0.sub_and_zero(&mut temp);
+        });
     }
 
     /// Moves the value of this cell into another cell, leaving a `false` behind in this cell.
+    #[track_caller]
     pub fn move_into(&mut self, other: &mut CellBool<N>) {
-        self.0.move_into(&mut other.0);
+        traced!(self.0.memory, "CellBool::move_into", {
+            self.0.move_into(&mut other.0);
+        });
     }
 
     /// Moves the value of another cell into this cell, leaving a `false` behind in the other cell.
+    #[track_caller]
     pub fn move_from(&mut self, other: &mut CellBool<N>) {
-        self.0.move_from(&mut other.0);
+        traced!(self.0.memory, "CellBool::move_from", {
+            self.0.move_from(&mut other.0);
+        });
     }
 
     /// Copies the value of this cell into another cell.
+    #[track_caller]
     pub fn copy_into(&self, other: &mut CellBool<N>) {
-        self.0.copy_into(&mut other.0);
+        traced!(self.0.memory, "CellBool::copy_into", {
+            self.0.copy_into(&mut other.0);
+        });
     }
 
     /// Copies the value of another cell into this cell.
+    #[track_caller]
     pub fn copy_from(&mut self, other: &CellBool<N>) {
-        self.0.copy_from(&other.0);
+        traced!(self.0.memory, "CellBool::copy_from", {
+            self.0.copy_from(&other.0);
+        });
     }
 }
 
@@ -104,316 +131,409 @@ impl<'a, const N: usize> IntoCell<'a, N> for bool {
 }
 
 impl<'a, const N: usize> Clone for CellBool<'a, N> {
+    #[track_caller]
     fn clone(&self) -> Self {
-        let mut output = self.0.memory.bool_uninit();
-        self.copy_into(&mut output);
-        output
+        traced!(self.0.memory, "CellBool::clone", {
+            let mut output = self.0.memory.bool_uninit();
+            self.copy_into(&mut output);
+            output
+        })
     }
 
+    #[track_caller]
     fn clone_from(&mut self, source: &Self) {
-        source.copy_into(self);
+        traced!(self.0.memory, "CellBool::clone_from", {
+            source.copy_into(self);
+        });
     }
 }
 
 impl<'a, const N: usize> Not for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn not(mut self) -> Self::Output {
-        self.negate();
-        self
+        traced!(self.0.memory, "CellBool::not", {
+            self.negate();
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Not for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn not(self) -> Self::Output {
-        let mut temp = self.0.memory.bool(true);
-        temp.0 -= &self.0;
-        temp
+        traced!(self.0.memory, "CellBool::not", {
+            let mut temp = self.0.memory.bool(true);
+            temp.0 -= &self.0;
+            temp
+        })
     }
 }
 
 impl<'a, const N: usize> BitOrAssign for CellBool<'a, N> {
+    #[track_caller]
     fn bitor_assign(&mut self, mut rhs: Self) {
-        rhs.0.add_and_zero(&mut self.0);
-        rhs.if_true(|| self.0.inc());
+        traced!(self.0.memory, "CellBool::bitor_assign", {
+            rhs.0.add_and_zero(&mut self.0);
+            rhs.if_true(|| self.0.inc());
+        });
     }
 }
 
 impl<'a, const N: usize> BitOrAssign<&CellBool<'a, N>> for CellBool<'a, N> {
+    #[track_caller]
     fn bitor_assign(&mut self, rhs: &CellBool<'a, N>) {
-        self.bitor_assign(rhs.clone());
+        traced!(self.0.memory, "CellBool::bitor_assign", {
+            self.bitor_assign(rhs.clone());
+        });
     }
 }
 
 impl<'a, const N: usize> BitOrAssign<bool> for CellBool<'a, N> {
+    #[track_caller]
     fn bitor_assign(&mut self, rhs: bool) {
-        if rhs {
-            self.set(true);
-        }
+        traced!(self.0.memory, "CellBool::bitor_assign", {
+            if rhs {
+                self.set(true);
+            }
+        });
     }
 }
 
 impl<'a, const N: usize> BitOr for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, rhs: Self) -> Self::Output {
-        let mut output = self.clone();
-        output |= rhs.clone();
-        output
+        traced!(self.0.memory, "CellBool::bitor", {
+            let mut output = self.clone();
+            output |= rhs.clone();
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> BitOr<CellBool<'a, N>> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, mut rhs: CellBool<'a, N>) -> Self::Output {
-        rhs |= self;
-        rhs
+        traced!(self.0.memory, "CellBool::bitor", {
+            rhs |= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> BitOr<&CellBool<'a, N>> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(mut self, rhs: &CellBool<'a, N>) -> Self::Output {
-        self |= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitor", {
+            self |= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitOr for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, mut rhs: CellBool<'a, N>) -> Self::Output {
-        rhs |= self;
-        rhs
+        traced!(self.0.memory, "CellBool::bitor", {
+            rhs |= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> BitOr<bool> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(mut self, rhs: bool) -> Self::Output {
-        self |= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitor", {
+            self |= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitOr<bool> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, rhs: bool) -> Self::Output {
-        self.clone() | rhs
+        traced!(self.0.memory, "CellBool::bitor", { self.clone() | rhs })
     }
 }
 
 impl<'a, const N: usize> BitOr<CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, rhs: CellBool<'a, N>) -> Self::Output {
-        rhs | self
+        traced!(rhs.0.memory, "CellBool::bitor", { rhs | self })
     }
 }
 
 impl<'a, const N: usize> BitOr<&CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitor(self, rhs: &CellBool<'a, N>) -> Self::Output {
-        rhs | self
+        traced!(rhs.0.memory, "CellBool::bitor", { rhs | self })
     }
 }
 
 impl<'a, const N: usize> BitAndAssign for CellBool<'a, N> {
+    #[track_caller]
     fn bitand_assign(&mut self, rhs: Self) {
-        let mut rhs = rhs.0;
-        // rhs = 0 (false) or 1 (true)
-        rhs.dec();
-        // rhs = 255 (false) or 0 (true)
-        rhs.while_nonzero_mut(|rhs| {
-            // rhs = 255 (false)
-            rhs.inc();
-            // rhs = 0 (false)
-            self.set(false);
+        traced!(self.0.memory, "CellBool::bitand_assign", {
+            let mut rhs = rhs.0;
+            // rhs = 0 (false) or 1 (true)
+            rhs.dec();
+            // rhs = 255 (false) or 0 (true)
+            rhs.while_nonzero_mut(|rhs| {
+                // rhs = 255 (false)
+                rhs.inc();
+                // rhs = 0 (false)
+                self.set(false);
+            });
         });
     }
 }
 
 impl<'a, const N: usize> BitAndAssign<&CellBool<'a, N>> for CellBool<'a, N> {
+    #[track_caller]
     fn bitand_assign(&mut self, rhs: &CellBool<'a, N>) {
-        let rhs = rhs.clone();
-        *self &= rhs;
+        traced!(self.0.memory, "CellBool::bitand_assign", {
+            let rhs = rhs.clone();
+            *self &= rhs;
+        });
     }
 }
 
 impl<'a, const N: usize> BitAndAssign<bool> for CellBool<'a, N> {
+    #[track_caller]
     fn bitand_assign(&mut self, rhs: bool) {
-        if !rhs {
-            self.set(false);
-        }
+        traced!(self.0.memory, "CellBool::bitand_assign", {
+            if !rhs {
+                self.set(false);
+            }
+        });
     }
 }
 
 impl<'a, const N: usize> BitAnd for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(mut self, rhs: Self) -> Self::Output {
-        self &= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitand", {
+            self &= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitAnd<CellBool<'a, N>> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(self, mut rhs: CellBool<'a, N>) -> Self::Output {
-        rhs &= self;
-        rhs
+        traced!(self.0.memory, "CellBool::bitand", {
+            rhs &= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> BitAnd<&CellBool<'a, N>> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(mut self, rhs: &CellBool<'a, N>) -> Self::Output {
-        self &= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitand", {
+            self &= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitAnd for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(self, rhs: Self) -> Self::Output {
-        let mut value = self.clone();
-        value &= rhs.clone();
-        value
+        traced!(self.0.memory, "CellBool::bitand", {
+            let mut value = self.clone();
+            value &= rhs.clone();
+            value
+        })
     }
 }
 
 impl<'a, const N: usize> BitAnd<bool> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(mut self, rhs: bool) -> Self::Output {
-        self &= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitand", {
+            self &= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitAnd<bool> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(self, rhs: bool) -> Self::Output {
-        self.clone() & rhs
+        traced!(self.0.memory, "CellBool::bitand", { self.clone() & rhs })
     }
 }
 
 impl<'a, const N: usize> BitAnd<CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(self, rhs: CellBool<'a, N>) -> Self::Output {
-        rhs & self
+        traced!(rhs.0.memory, "CellBool::bitand", { rhs & self })
     }
 }
 
 impl<'a, const N: usize> BitAnd<&CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitand(self, rhs: &CellBool<'a, N>) -> Self::Output {
-        rhs & self
+        traced!(rhs.0.memory, "CellBool::bitand", { rhs & self })
     }
 }
 
 impl<'a, const N: usize> BitXorAssign for CellBool<'a, N> {
+    #[track_caller]
     fn bitxor_assign(&mut self, rhs: Self) {
-        rhs.if_true(|| {
-            self.negate();
+        traced!(self.0.memory, "CellBool::bitxor_assign", {
+            rhs.if_true(|| {
+                self.negate();
+            });
         });
     }
 }
 
 impl<'a, const N: usize> BitXorAssign<&CellBool<'a, N>> for CellBool<'a, N> {
+    #[track_caller]
     fn bitxor_assign(&mut self, rhs: &CellBool<'a, N>) {
-        rhs.clone().if_true(|| {
-            self.negate();
-        })
+        traced!(self.0.memory, "CellBool::bitxor_assign", {
+            rhs.clone().if_true(|| {
+                self.negate();
+            })
+        });
     }
 }
 
 impl<'a, const N: usize> BitXorAssign<bool> for CellBool<'a, N> {
+    #[track_caller]
     fn bitxor_assign(&mut self, rhs: bool) {
-        if rhs {
-            self.negate();
-        }
+        traced!(self.0.memory, "CellBool::bitxor_assign", {
+            if rhs {
+                self.negate();
+            }
+        });
     }
 }
 
 impl<'a, const N: usize> BitXor for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(mut self, rhs: CellBool<'a, N>) -> Self::Output {
-        self ^= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitxor", {
+            self ^= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitXor<&CellBool<'a, N>> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(mut self, rhs: &CellBool<'a, N>) -> Self::Output {
-        self ^= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitxor", {
+            self ^= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitXor<CellBool<'a, N>> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(self, mut rhs: CellBool<'a, N>) -> Self::Output {
-        rhs ^= self;
-        rhs
+        traced!(self.0.memory, "CellBool::bitxor", {
+            rhs ^= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> BitXor for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(self, rhs: &CellBool<'a, N>) -> Self::Output {
-        self.clone() ^ rhs
+        traced!(self.0.memory, "CellBool::bitxor", { self.clone() ^ rhs })
     }
 }
 
 impl<'a, const N: usize> BitXor<bool> for CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(mut self, rhs: bool) -> Self::Output {
-        self ^= rhs;
-        self
+        traced!(self.0.memory, "CellBool::bitxor", {
+            self ^= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> BitXor<bool> for &CellBool<'a, N> {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(self, rhs: bool) -> Self::Output {
-        self.clone() ^ rhs
+        traced!(self.0.memory, "CellBool::bitxor", { self.clone() ^ rhs })
     }
 }
 
 impl<'a, const N: usize> BitXor<CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(self, mut rhs: CellBool<'a, N>) -> Self::Output {
-        rhs ^= self;
-        rhs
+        traced!(rhs.0.memory, "CellBool::bitxor", {
+            rhs ^= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> BitXor<&CellBool<'a, N>> for bool {
     type Output = CellBool<'a, N>;
 
+    #[track_caller]
     fn bitxor(self, rhs: &CellBool<'a, N>) -> Self::Output {
-        rhs.clone() ^ self
+        traced!(rhs.0.memory, "CellBool::bitxor", { rhs.clone() ^ self })
     }
 }
@@ -3,9 +3,15 @@
 use super::bool::CellBool;
 use super::cell::IntoCell;
 use super::u8::CellU8;
-use crate::builder::tracking::TrackingBuilder;
-use crate::{compiler::Program, runner::Runner};
-use std::cell::{Cell, RefCell};
+use crate::builder::{source_map::SourceMap, tracking::TrackingBuilder};
+use crate::{
+    compiler::{error::ParseError, execution::Outcome, Program},
+    runner::{Eof, Runner, RunnerOptions},
+};
+use std::{
+    cell::{Cell, RefCell},
+    panic::Location,
+};
 
 #[derive(Debug)]
 /// A builder which allocates memory into cells and releases them automatically.
@@ -22,6 +28,12 @@ pub struct AllocatingBuilder<const N: usize> {
     earliest_open_space: Cell<usize>,
 }
 
+impl<const N: usize> Default for AllocatingBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const N: usize> AllocatingBuilder<N> {
     /// Creates a new builder.
     pub fn new() -> Self {
@@ -33,13 +45,24 @@ impl<const N: usize> AllocatingBuilder<N> {
     }
 
     /// Compiles the source of this builder, returning an error if it is malformed.
-    pub fn compile(&self) -> Result<Program, &'static str> {
+    pub fn compile(&self) -> Result<Program, ParseError> {
         self.builder.borrow().compile()
     }
 
     /// Runs the code in this builder on a given input, returning an error if it is malformed.
-    pub fn run(&self, input: &[u8]) -> Result<Runner<N>, &'static str> {
-        self.compile().map(|program| program.run::<N>(input))
+    pub fn run(&self, input: &[u8]) -> Result<Runner<N>, ParseError> {
+        self.compile().map(|program| program.run::<N, u8>(input))
+    }
+
+    /// Runs the code in this builder on a given input using custom runner options, returning an
+    /// error if it is malformed. Otherwise, returns why the program stopped along with the runner.
+    pub fn run_with_options(
+        &self,
+        input: &[u8],
+        options: RunnerOptions,
+    ) -> Result<Outcome<N>, ParseError> {
+        self.compile()
+            .map(|program| program.run_with_options::<N, u8>(input, options))
     }
 
     /// Gets the source of this builder. Returns an owned `String` due to technicalities.
@@ -47,14 +70,41 @@ impl<const N: usize> AllocatingBuilder<N> {
         self.builder.borrow().source().to_owned()
     }
 
+    /// Gets the source map of this builder, which attributes each part of its source to the
+    /// operation which emitted it. Returns an owned `SourceMap` for the same reason as `source`.
+    pub fn source_map(&self) -> SourceMap {
+        self.builder.borrow().source_map().clone()
+    }
+
+    /// Runs an operation, recording the source it emits in the source map under a name.
+    #[track_caller]
+    pub(super) fn operation<R>(&self, name: &'static str, f: impl FnOnce() -> R) -> R {
+        {
+            let mut builder = self.builder.borrow_mut();
+            let start = builder.source().len();
+            builder
+                .source_map_mut()
+                .open(name, Location::caller(), start);
+        }
+
+        let result = f();
+
+        let mut builder = self.builder.borrow_mut();
+        let end = builder.source().len();
+        builder.source_map_mut().close(end);
+
+        result
+    }
+
     /// Allocates a byte of memory, returning its location.
     fn allocate(&self) -> usize {
         let mut memory = self.memory.get();
         let location = self.earliest_open_space.get();
         memory[location] = true;
+        self.memory.set(memory);
 
-        for index in location + 1..N {
-            if !memory[index] {
+        for (index, used) in memory.iter().enumerate().skip(location + 1) {
+            if !used {
                 self.earliest_open_space.set(index);
                 return location;
             }
@@ -67,6 +117,7 @@ impl<const N: usize> AllocatingBuilder<N> {
     pub(super) fn deallocate(&self, location: usize) {
         let mut memory = self.memory.get();
         memory[location] = false;
+        self.memory.set(memory);
 
         let previous_earliest_open_space = self.earliest_open_space.get();
         self.earliest_open_space
@@ -79,7 +130,7 @@ impl<const N: usize> AllocatingBuilder<N> {
     }
 
     /// Allocates an uninitialized `u8` value. Its value is not guaranteed to be zero.
-    pub(super) fn u8_uninit(&self) -> CellU8<N> {
+    pub(super) fn u8_uninit(&self) -> CellU8<'_, N> {
         let location = self.allocate();
 
         CellU8 {
@@ -89,29 +140,124 @@ impl<const N: usize> AllocatingBuilder<N> {
     }
 
     /// Allocates a `u8` and gives it a defined value.
-    pub fn u8(&self, value: u8) -> CellU8<N> {
-        let mut cell = self.u8_uninit();
-        cell.set(value);
-        cell
+    #[track_caller]
+    pub fn u8(&self, value: u8) -> CellU8<'_, N> {
+        self.operation("AllocatingBuilder::u8", || {
+            let mut cell = self.u8_uninit();
+            cell.set(value);
+            cell
+        })
     }
 
     /// Allocates a `u8` and initializes it with the next input value.
-    pub fn read(&self) -> CellU8<N> {
-        let mut cell = self.u8_uninit();
-        cell.read();
-        cell
+    #[track_caller]
+    pub fn read(&self) -> CellU8<'_, N> {
+        self.operation("AllocatingBuilder::read", || {
+            let mut cell = self.u8_uninit();
+            cell.read();
+            cell
+        })
+    }
+
+    /// Reads input one byte at a time until input is exhausted, running `f` on each byte. The code
+    /// emitted depends on the end-of-input convention the program will be run with, so `eof` must
+    /// match the runner's `eof` option. Under `Eof::Zero` and `Eof::Unchanged`, a zero byte in the
+    /// input is indistinguishable from the end of input and also stops the loop. Likewise, under
+    /// `Eof::MinusOne`, a `0xFF` byte reads as -1 in byte cells and stops the loop.
+    #[track_caller]
+    pub fn read_until_eof(&self, eof: Eof, f: impl FnOnce(&CellU8<'_, N>)) {
+        self.operation("AllocatingBuilder::read_until_eof", || {
+            let mut cell = self.u8_uninit();
+
+            let read = |cell: &mut CellU8<'_, N>| match eof {
+                Eof::Zero => cell.read(),
+
+                Eof::MinusOne => {
+                    cell.read();
+                    cell.inc();
+                }
+
+                Eof::Unchanged => {
+                    cell.zero();
+                    cell.read();
+                }
+            };
+
+            read(&mut cell);
+
+            cell.while_nonzero_mut(|cell| {
+                if eof == Eof::MinusOne {
+                    cell.dec();
+                }
+
+                f(cell);
+                read(cell);
+            });
+        });
     }
 
     /// Allocates an uninitialized `bool` value. Its value is not guaranteed to be false, or even to
     /// be a valid boolean.
-    pub(super) fn bool_uninit(&self) -> CellBool<N> {
+    pub(super) fn bool_uninit(&self) -> CellBool<'_, N> {
         CellBool(self.u8_uninit())
     }
 
     /// Allocates a `bool` and gives it a defined value.
-    pub fn bool(&self, value: bool) -> CellBool<N> {
-        let mut cell = self.bool_uninit();
-        cell.set(value);
-        cell
+    #[track_caller]
+    pub fn bool(&self, value: bool) -> CellBool<'_, N> {
+        self.operation("AllocatingBuilder::bool", || {
+            let mut cell = self.bool_uninit();
+            cell.set(value);
+            cell
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AllocatingBuilder;
+    use crate::{
+        compiler::execution::Limit,
+        runner::{Eof, RunnerOptions},
+    };
+
+    /// Builds a program which echoes its input until the end of input, run under `eof`.
+    fn echo(eof: Eof, input: &[u8]) -> Vec<u8> {
+        let builder = AllocatingBuilder::<16>::new();
+
+        // Leave a nonzero value in the cell the loop reads into, which `Eof::Unchanged` would
+        // otherwise keep reading as another byte.
+        drop(builder.u8(7));
+
+        builder.read_until_eof(eof, |cell| cell.write());
+
+        let options = RunnerOptions {
+            eof,
+            ..RunnerOptions::default()
+        };
+
+        // A loop which misses the end of input never stops, so it's run out of fuel instead.
+        let program = builder.compile().unwrap();
+        let outcome = program.run_limited::<16, u8>(input, options, Limit::fuel(10_000));
+        assert!(outcome.halted(), "{eof:?} didn't stop on {input:?}");
+        outcome.runner.output().clone()
+    }
+
+    #[test]
+    fn read_until_eof_stops_at_the_end_of_input() {
+        for eof in [Eof::Zero, Eof::MinusOne, Eof::Unchanged] {
+            assert_eq!(echo(eof, b"hello"), b"hello", "{eof:?}");
+            assert_eq!(echo(eof, b""), b"", "{eof:?}");
+        }
+    }
+
+    #[test]
+    fn read_until_eof_stops_at_bytes_which_read_like_eof() {
+        assert_eq!(echo(Eof::Zero, b"ab\0cd"), b"ab");
+        assert_eq!(echo(Eof::Unchanged, b"ab\0cd"), b"ab");
+        assert_eq!(echo(Eof::MinusOne, b"ab\xFFcd"), b"ab");
+
+        // A zero byte is only the end of input under conventions which store zero there.
+        assert_eq!(echo(Eof::MinusOne, b"ab\0cd"), b"ab\0cd");
     }
 }
@@ -1,5 +1,14 @@
 //! This module defines a builder which allocates memory into cells and releases them automatically.
 
+/// Runs a block as an operation of a cell's builder, recording the source it emits in the source
+/// map under a name. The builder is copied out first, so the block can move the cell.
+macro_rules! traced {
+    ($memory:expr, $name:literal, $body:block) => {{
+        let memory = $memory;
+        memory.operation($name, move || $body)
+    }};
+}
+
 pub mod array;
 pub mod bool;
 pub mod cell;
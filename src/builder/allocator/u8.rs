@@ -25,199 +25,260 @@ impl<'a, const N: usize> CellU8<'a, N> {
     }
 
     /// Reads a value from input into this cell.
+    #[track_caller]
     pub fn read(&mut self) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.read();
+        traced!(self.memory, "CellU8::read", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.read();
+        });
     }
 
     /// Writes the value from this cell into output.
+    #[track_caller]
     pub fn write(&self) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.write();
+        traced!(self.memory, "CellU8::write", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.write();
+        });
     }
 
     /// Sets the value of this cell to zero.
+    #[track_caller]
     pub fn zero(&mut self) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.zero();
+        traced!(self.memory, "CellU8::zero", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.zero();
+        });
     }
 
     /// Sets the value of this cell to a `u8` value.
+    #[track_caller]
     pub fn set(&mut self, value: u8) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.set(value);
+        traced!(self.memory, "CellU8::set", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.set(value);
+        });
     }
 
     /// Increments the value of this cell.
+    #[track_caller]
     pub fn inc(&mut self) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.inc();
+        traced!(self.memory, "CellU8::inc", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.inc();
+        });
     }
 
     /// Increments the value of this cell by a `u8` value.
+    #[track_caller]
     pub fn inc_by(&mut self, value: u8) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.inc_by(value);
+        traced!(self.memory, "CellU8::inc_by", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.inc_by(value);
+        });
     }
 
     /// Decrements the value of this cell.
+    #[track_caller]
     pub fn dec(&mut self) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.dec();
+        traced!(self.memory, "CellU8::dec", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.dec();
+        });
     }
 
     /// Decrements the value of this cell by a `u8` value.
+    #[track_caller]
     pub fn dec_by(&mut self, value: u8) {
-        let mut builder = self.borrow_builder_mut();
-        builder.goto(self.location);
-        builder.dec_by(value);
+        traced!(self.memory, "CellU8::dec_by", {
+            let mut builder = self.borrow_builder_mut();
+            builder.goto(self.location);
+            builder.dec_by(value);
+        });
     }
 
     /// Creates a loop while this cell value is nonzero.
+    #[track_caller]
     pub fn while_nonzero(&self, f: impl FnOnce(&Self)) {
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.location);
-            builder.source_mut().push('[');
-        }
-
-        f(self);
-
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.location);
-            builder.source_mut().push(']');
-        }
+        traced!(self.memory, "CellU8::while_nonzero", {
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.location);
+                builder.source_mut().push('[');
+            }
+
+            f(self);
+
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.location);
+                builder.source_mut().push(']');
+            }
+        });
     }
 
     /// Creates a loop while this cell value is nonzero.
+    #[track_caller]
     pub fn while_nonzero_mut(&mut self, f: impl FnOnce(&mut Self)) {
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.location);
-            builder.source_mut().push('[');
-        }
-
-        f(self);
-
-        {
-            let mut builder = self.borrow_builder_mut();
-            builder.goto(self.location);
-            builder.source_mut().push(']');
-        }
+        traced!(self.memory, "CellU8::while_nonzero_mut", {
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.location);
+                builder.source_mut().push('[');
+            }
+
+            f(self);
+
+            {
+                let mut builder = self.borrow_builder_mut();
+                builder.goto(self.location);
+                builder.source_mut().push(']');
+            }
+        });
     }
 
     /// Moves the value of this cell into another cell, leaving a `0` behind in this cell.
+    #[track_caller]
     pub fn move_into(&mut self, other: &mut CellU8<N>) {
-        other.zero();
+        traced!(self.memory, "CellU8::move_into", {
+            other.zero();
 
-        self.while_nonzero_mut(|this| {
-            this.dec();
-            other.inc();
-        })
+            self.while_nonzero_mut(|this| {
+                this.dec();
+                other.inc();
+            })
+        });
     }
 
     /// Moves the value of another cell into this cell, leaving a `0` behind in the other cell.
+    #[track_caller]
     pub fn move_from(&mut self, other: &mut CellU8<N>) {
-        other.move_into(self);
+        traced!(self.memory, "CellU8::move_from", {
+            other.move_into(self);
+        });
     }
 
     /// Copies the value of this cell into another cell.
+    #[track_caller]
     pub fn copy_into(&self, other: &mut CellU8<N>) {
-        let temp = self.memory.u8(0);
+        traced!(self.memory, "CellU8::copy_into", {
+            let temp = self.memory.u8(0);
+            other.zero();
 
-        // We have to resort to a low-level implementation here because all the methods that we need
-        // mutate `self`, but we only have a regular reference.
+            // We have to resort to a low-level implementation here because all the methods that we need
+            // mutate `self`, but we only have a regular reference.
 
-        let mut builder = self.borrow_builder_mut();
+            let mut builder = self.borrow_builder_mut();
 
-        // This moves `self` into `temp`, leaving `self` as 0.
-        builder.repeat_at(self.location, |builder| {
-            builder.dec();
-            builder.goto(temp.location);
-            builder.inc();
-        });
+            // This moves `self` into `temp`, leaving `self` as 0.
+            builder.repeat_at(self.location, |builder| {
+                builder.dec();
+                builder.goto(temp.location);
+                builder.inc();
+            });
 
-        // This moves `temp` into `self` and `other`, leaving it as 0.
-        builder.repeat_at(temp.location, |builder| {
-            builder.dec();
-            builder.goto(self.location);
-            builder.inc();
-            builder.goto(other.location);
-            builder.inc();
+            // This moves `temp` into `self` and `other`, leaving it as 0.
+            builder.repeat_at(temp.location, |builder| {
+                builder.dec();
+                builder.goto(self.location);
+                builder.inc();
+                builder.goto(other.location);
+                builder.inc();
+            });
         });
     }
 
     /// Copies the value of another cell into this cell.
+    #[track_caller]
     pub fn copy_from(&mut self, other: &CellU8<N>) {
-        other.copy_into(self);
+        traced!(self.memory, "CellU8::copy_from", {
+            other.copy_into(self);
+        });
     }
 
     /// Adds the value of `other` into `self`, zeroing `other` in the process.
+    #[track_caller]
     pub fn add_and_zero(&mut self, other: &mut CellU8<N>) {
-        other.while_nonzero_mut(|other| {
-            self.inc();
-            other.dec();
+        traced!(self.memory, "CellU8::add_and_zero", {
+            other.while_nonzero_mut(|other| {
+                self.inc();
+                other.dec();
+            });
         });
     }
 
     /// Subtracts the value of `other` from `self`, zeroing `other` in the process.
+    #[track_caller]
     pub fn sub_and_zero(&mut self, other: &mut CellU8<N>) {
-        other.while_nonzero_mut(|other| {
-            self.dec();
-            other.dec();
+        traced!(self.memory, "CellU8::sub_and_zero", {
+            other.while_nonzero_mut(|other| {
+                self.dec();
+                other.dec();
+            });
         });
     }
 
     /// Returns a `CellBool` indicating if `self` is nonzero.
+    #[track_caller]
     pub fn is_nonzero(mut self) -> CellBool<'a, N> {
-        let mut output = self.memory.bool(false);
+        traced!(self.memory, "CellU8::is_nonzero", {
+            let mut output = self.memory.bool(false);
 
-        self.while_nonzero_mut(|this| {
-            this.zero();
-            output.0.inc();
-        });
+            self.while_nonzero_mut(|this| {
+                this.zero();
+                output.0.inc();
+            });
 
-        output
+            output
+        })
     }
 
     /// Returns a `CellBool` indicating if `self` is zero.
+    #[track_caller]
     pub fn is_zero(mut self) -> CellBool<'a, N> {
-        let mut output = self.memory.bool(true);
+        traced!(self.memory, "CellU8::is_zero", {
+            let mut output = self.memory.bool(true);
 
-        self.while_nonzero_mut(|this| {
-            this.zero();
-            output.0.dec();
-        });
+            self.while_nonzero_mut(|this| {
+                this.zero();
+                output.0.dec();
+            });
 
-        output
+            output
+        })
     }
 
     /// Swaps the values of `self` and `other`.
+    #[track_caller]
     pub fn swap(&mut self, other: &mut CellU8<N>) {
-        let mut temp = self.memory.u8_uninit();
-        other.move_into(&mut temp);
-        self.move_into(other);
-        temp.move_into(self);
+        traced!(self.memory, "CellU8::swap", {
+            let mut temp = self.memory.u8_uninit();
+            other.move_into(&mut temp);
+            self.move_into(other);
+            temp.move_into(self);
+        });
     }
 
     /// Squares `self` in place.
+    #[track_caller]
     pub fn square(&mut self) {
-        let mut temp0 = self.memory.u8_uninit();
-        self.move_into(&mut temp0);
-
-        temp0.while_nonzero_mut(|temp0| {
-            temp0.dec();
-            *self += &*temp0;
-            *self += &*temp0;
-            self.inc();
+        traced!(self.memory, "CellU8::square", {
+            let mut temp0 = self.memory.u8_uninit();
+            self.move_into(&mut temp0);
+
+            temp0.while_nonzero_mut(|temp0| {
+                temp0.dec();
+                *self += &*temp0;
+                *self += &*temp0;
+                self.inc();
+            });
         });
     }
 }
@@ -231,14 +292,20 @@ impl<'a, const N: usize> IntoCell<'a, N> for u8 {
 }
 
 impl<'a, const N: usize> Clone for CellU8<'a, N> {
+    #[track_caller]
     fn clone(&self) -> Self {
-        let mut output = self.memory.u8_uninit();
-        self.copy_into(&mut output);
-        output
+        traced!(self.memory, "CellU8::clone", {
+            let mut output = self.memory.u8_uninit();
+            self.copy_into(&mut output);
+            output
+        })
     }
 
+    #[track_caller]
     fn clone_from(&mut self, source: &Self) {
-        source.copy_into(self);
+        traced!(self.memory, "CellU8::clone_from", {
+            source.copy_into(self);
+        });
     }
 }
 
@@ -249,415 +316,561 @@ impl<'a, const N: usize> Drop for CellU8<'a, N> {
 }
 
 impl<'a, const N: usize> AddAssign<u8> for CellU8<'a, N> {
+    #[track_caller]
     fn add_assign(&mut self, rhs: u8) {
-        self.inc_by(rhs);
+        traced!(self.memory, "CellU8::add_assign", {
+            self.inc_by(rhs);
+        });
     }
 }
 
 impl<'a, const N: usize> AddAssign<&CellU8<'a, N>> for CellU8<'a, N> {
+    #[track_caller]
     fn add_assign(&mut self, rhs: &CellU8<'a, N>) {
-        let mut temp = rhs.clone();
-        self.add_and_zero(&mut temp);
+        traced!(self.memory, "CellU8::add_assign", {
+            let mut temp = rhs.clone();
+            self.add_and_zero(&mut temp);
+        });
     }
 }
 
 impl<'a, const N: usize> AddAssign for CellU8<'a, N> {
+    #[track_caller]
     fn add_assign(&mut self, mut rhs: CellU8<'a, N>) {
-        self.add_and_zero(&mut rhs);
+        traced!(self.memory, "CellU8::add_assign", {
+            self.add_and_zero(&mut rhs);
+        });
     }
 }
 
 impl<'a, const N: usize> SubAssign<u8> for CellU8<'a, N> {
+    #[track_caller]
     fn sub_assign(&mut self, rhs: u8) {
-        self.dec_by(rhs);
+        traced!(self.memory, "CellU8::sub_assign", {
+            self.dec_by(rhs);
+        });
     }
 }
 
 impl<'a, const N: usize> SubAssign<&CellU8<'a, N>> for CellU8<'a, N> {
+    #[track_caller]
     fn sub_assign(&mut self, rhs: &CellU8<'a, N>) {
-        let mut temp = rhs.clone();
-        self.sub_and_zero(&mut temp);
+        traced!(self.memory, "CellU8::sub_assign", {
+            let mut temp = rhs.clone();
+            self.sub_and_zero(&mut temp);
+        });
     }
 }
 
 impl<'a, const N: usize> SubAssign for CellU8<'a, N> {
+    #[track_caller]
     fn sub_assign(&mut self, mut rhs: CellU8<'a, N>) {
-        self.sub_and_zero(&mut rhs);
+        traced!(self.memory, "CellU8::sub_assign", {
+            self.sub_and_zero(&mut rhs);
+        });
     }
 }
 
 impl<'a, const N: usize> Add<u8> for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(self, rhs: u8) -> Self::Output {
-        let mut output = self.clone();
-        output += rhs;
-        output
+        traced!(self.memory, "CellU8::add", {
+            let mut output = self.clone();
+            output += rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Add<&CellU8<'a, N>> for u8 {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(self, rhs: &CellU8<'a, N>) -> Self::Output {
-        let mut output = rhs.clone();
-        output += self;
-        output
+        traced!(rhs.memory, "CellU8::add", {
+            let mut output = rhs.clone();
+            output += self;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Add<u8> for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(mut self, rhs: u8) -> Self::Output {
-        self += rhs;
-        self
+        traced!(self.memory, "CellU8::add", {
+            self += rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Add<CellU8<'a, N>> for u8 {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(self, mut rhs: CellU8<'a, N>) -> Self::Output {
-        rhs += self;
-        rhs
+        traced!(rhs.memory, "CellU8::add", {
+            rhs += self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> Add for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(self, rhs: Self) -> Self::Output {
-        let mut output = self.clone();
-        output += rhs;
-        output
+        traced!(self.memory, "CellU8::add", {
+            let mut output = self.clone();
+            output += rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Add<&CellU8<'a, N>> for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(mut self, rhs: &CellU8<'a, N>) -> Self::Output {
-        self += rhs;
-        self
+        traced!(self.memory, "CellU8::add", {
+            self += rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Add<CellU8<'a, N>> for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(self, mut rhs: CellU8<'a, N>) -> Self::Output {
-        rhs += self;
-        rhs
+        traced!(self.memory, "CellU8::add", {
+            rhs += self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> Add for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn add(mut self, mut rhs: CellU8<'a, N>) -> Self::Output {
-        self.add_and_zero(&mut rhs);
-        self
+        traced!(self.memory, "CellU8::add", {
+            self.add_and_zero(&mut rhs);
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<u8> for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(self, rhs: u8) -> Self::Output {
-        let mut output = self.clone();
-        output -= rhs;
-        output
+        traced!(self.memory, "CellU8::sub", {
+            let mut output = self.clone();
+            output -= rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<&CellU8<'a, N>> for u8 {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(self, rhs: &CellU8<'a, N>) -> Self::Output {
-        let mut output = rhs.memory.u8(self);
-        output -= rhs;
-        output
+        traced!(rhs.memory, "CellU8::sub", {
+            let mut output = rhs.memory.u8(self);
+            output -= rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<u8> for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(mut self, rhs: u8) -> Self::Output {
-        self -= rhs;
-        self
+        traced!(self.memory, "CellU8::sub", {
+            self -= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<CellU8<'a, N>> for u8 {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(self, rhs: CellU8<'a, N>) -> Self::Output {
-        let mut output = rhs.memory.u8(self);
-        output -= rhs;
-        output
+        traced!(rhs.memory, "CellU8::sub", {
+            let mut output = rhs.memory.u8(self);
+            output -= rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Sub for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(self, rhs: Self) -> Self::Output {
-        let mut output = self.clone();
-        output -= rhs;
-        output
+        traced!(self.memory, "CellU8::sub", {
+            let mut output = self.clone();
+            output -= rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<&CellU8<'a, N>> for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(mut self, rhs: &CellU8<'a, N>) -> Self::Output {
-        self -= rhs;
-        self
+        traced!(self.memory, "CellU8::sub", {
+            self -= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Sub<CellU8<'a, N>> for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(self, rhs: CellU8<'a, N>) -> Self::Output {
-        let mut output = self.clone();
-        output -= rhs;
-        output
+        traced!(self.memory, "CellU8::sub", {
+            let mut output = self.clone();
+            output -= rhs;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Sub for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn sub(mut self, mut rhs: CellU8<'a, N>) -> Self::Output {
-        self.sub_and_zero(&mut rhs);
-        self
+        traced!(self.memory, "CellU8::sub", {
+            self.sub_and_zero(&mut rhs);
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Neg for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn neg(self) -> Self::Output {
-        let mut output = self.memory.u8(0);
-        output -= self;
-        output
+        traced!(self.memory, "CellU8::neg", {
+            let mut output = self.memory.u8(0);
+            output -= self;
+            output
+        })
     }
 }
 
 impl<'a, const N: usize> Neg for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn neg(mut self) -> Self::Output {
-        let mut temp = self.memory.u8_uninit();
-        self.move_into(&mut temp);
-        self -= temp;
-        self
+        traced!(self.memory, "CellU8::neg", {
+            let mut temp = self.memory.u8_uninit();
+            self.move_into(&mut temp);
+            self -= temp;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> PartialEq<'a, N, u8> for CellU8<'a, N> {
+    #[track_caller]
     fn eq(mut self, other: u8) -> CellBool<'a, N> {
-        self -= other;
-        self.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            self -= other;
+            self.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(mut self, other: u8) -> CellBool<'a, N> {
-        self -= other;
-        self.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            self -= other;
+            self.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N, u8> for CellU8<'a, N> {}
 
 impl<'a, const N: usize> PartialEq<'a, N, u8> for &CellU8<'a, N> {
+    #[track_caller]
     fn eq(self, other: u8) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(self, other: u8) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N, u8> for &CellU8<'a, N> {}
 
 impl<'a, const N: usize> PartialEq<'a, N> for &CellU8<'a, N> {
+    #[track_caller]
     fn eq(self, other: Self) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(self, other: Self) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N> for &CellU8<'a, N> {}
 
 impl<'a, const N: usize> PartialEq<'a, N, CellU8<'a, N>> for &CellU8<'a, N> {
+    #[track_caller]
     fn eq(self, mut other: CellU8<'a, N>) -> CellBool<'a, N> {
-        other -= self;
-        other.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            other -= self;
+            other.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(self, mut other: CellU8<'a, N>) -> CellBool<'a, N> {
-        other -= self;
-        other.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            other -= self;
+            other.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N, CellU8<'a, N>> for &CellU8<'a, N> {}
 
 impl<'a, const N: usize> PartialEq<'a, N, &CellU8<'a, N>> for CellU8<'a, N> {
+    #[track_caller]
     fn eq(self, other: &CellU8<'a, N>) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(self, other: &CellU8<'a, N>) -> CellBool<'a, N> {
-        let mut output = self.clone();
-        output -= other;
-        output.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            let mut output = self.clone();
+            output -= other;
+            output.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N, &CellU8<'a, N>> for CellU8<'a, N> {}
 
 impl<'a, const N: usize> PartialEq<'a, N> for CellU8<'a, N> {
+    #[track_caller]
     fn eq(mut self, other: Self) -> CellBool<'a, N> {
-        self -= other;
-        self.is_zero()
+        traced!(self.memory, "CellU8::eq", {
+            self -= other;
+            self.is_zero()
+        })
     }
 
+    #[track_caller]
     fn ne(mut self, other: Self) -> CellBool<'a, N> {
-        self -= other;
-        self.is_nonzero()
+        traced!(self.memory, "CellU8::ne", {
+            self -= other;
+            self.is_nonzero()
+        })
     }
 }
 
 impl<'a, const N: usize> Eq<'a, N> for CellU8<'a, N> {}
 
 impl<'a, const N: usize> MulAssign<&CellU8<'a, N>> for CellU8<'a, N> {
+    #[track_caller]
     fn mul_assign(&mut self, rhs: &CellU8<'a, N>) {
-        let mut x = self.memory.u8_uninit();
-        x.move_from(self);
+        traced!(self.memory, "CellU8::mul_assign", {
+            let mut x = self.memory.u8_uninit();
+            x.move_from(self);
 
-        x.while_nonzero_mut(|x| {
-            x.dec();
-            *self += rhs;
+            x.while_nonzero_mut(|x| {
+                x.dec();
+                *self += rhs;
+            });
         });
     }
 }
 
 impl<'a, const N: usize> MulAssign for CellU8<'a, N> {
+    #[track_caller]
     fn mul_assign(&mut self, rhs: Self) {
-        *self *= &rhs;
+        traced!(self.memory, "CellU8::mul_assign", {
+            *self *= &rhs;
+        });
     }
 }
 
 impl<'a, const N: usize> Mul for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn mul(mut self, rhs: Self) -> Self::Output {
-        self *= rhs;
-        self
+        traced!(self.memory, "CellU8::mul", {
+            self *= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Mul<CellU8<'a, N>> for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn mul(self, mut rhs: CellU8<'a, N>) -> Self::Output {
-        rhs *= self;
-        rhs
+        traced!(self.memory, "CellU8::mul", {
+            rhs *= self;
+            rhs
+        })
     }
 }
 
 impl<'a, const N: usize> Mul<&CellU8<'a, N>> for CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn mul(mut self, rhs: &CellU8<'a, N>) -> Self::Output {
-        self *= rhs;
-        self
+        traced!(self.memory, "CellU8::mul", {
+            self *= rhs;
+            self
+        })
     }
 }
 
 impl<'a, const N: usize> Mul for &CellU8<'a, N> {
     type Output = CellU8<'a, N>;
 
+    #[track_caller]
     fn mul(self, rhs: Self) -> Self::Output {
-        self.clone() * rhs
+        traced!(self.memory, "CellU8::mul", { self.clone() * rhs })
     }
 }
 
 impl<'a, const N: usize> DivAssign<&CellU8<'a, N>> for CellU8<'a, N> {
+    #[track_caller]
     fn div_assign(&mut self, rhs: &CellU8<'a, N>) {
-        let mut temp0 = self.memory.u8(0);
-        let mut temp1 = self.memory.u8(0);
-        let mut temp2 = self.memory.u8(0);
-        let mut temp3 = self.memory.u8(0);
-        let mut rhs = rhs.clone();
-
-        self.while_nonzero_mut(|this| {
-            temp0.inc();
-            this.dec();
-        });
-
-        temp0.while_nonzero_mut(|temp0| {
-            rhs.while_nonzero_mut(|y| {
-                temp1.inc();
-                temp2.inc();
-                y.dec();
+        traced!(self.memory, "CellU8::div_assign", {
+            let mut temp0 = self.memory.u8(0);
+            let mut temp1 = self.memory.u8(0);
+            let mut temp2 = self.memory.u8(0);
+            let mut temp3 = self.memory.u8(0);
+            let mut rhs = rhs.clone();
+
+            self.while_nonzero_mut(|this| {
+                temp0.inc();
+                this.dec();
             });
 
-            temp2.while_nonzero_mut(|temp2| {
-                rhs.inc();
-                temp2.dec();
-            });
+            temp0.while_nonzero_mut(|temp0| {
+                rhs.while_nonzero_mut(|y| {
+                    temp1.inc();
+                    temp2.inc();
+                    y.dec();
+                });
 
-            temp1.while_nonzero_mut(|temp1| {
-                temp2.inc();
-                temp0.dec();
+                temp2.while_nonzero_mut(|temp2| {
+                    rhs.inc();
+                    temp2.dec();
+                });
 
-                temp0.while_nonzero_mut(|temp0| {
-                    temp2.zero();
-                    temp3.inc();
+                temp1.while_nonzero_mut(|temp1| {
+                    temp2.inc();
                     temp0.dec();
-                });
 
-                temp3.while_nonzero_mut(|temp3| {
-                    temp0.inc();
-                    temp3.dec();
-                });
+                    temp0.while_nonzero_mut(|temp0| {
+                        temp2.zero();
+                        temp3.inc();
+                        temp0.dec();
+                    });
 
-                temp2.while_nonzero_mut(|temp2| {
-                    temp1.dec();
-                    temp1.while_nonzero_mut(|temp1| {
-                        self.dec();
-                        temp1.zero();
+                    temp3.while_nonzero_mut(|temp3| {
+                        temp0.inc();
+                        temp3.dec();
                     });
-                    temp1.inc();
-                    temp2.dec();
+
+                    temp2.while_nonzero_mut(|temp2| {
+                        temp1.dec();
+                        temp1.while_nonzero_mut(|temp1| {
+                            self.dec();
+                            temp1.zero();
+                        });
+                        temp1.inc();
+                        temp2.dec();
+                    });
+
+                    temp1.dec();
                 });
 
-                temp1.dec();
+                self.inc();
             });
 
-            self.inc();
+            // x/y = (x-y)/y
         });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::allocator::core::AllocatingBuilder;
+
+    #[test]
+    fn copying_overwrites_the_target() {
+        let builder = AllocatingBuilder::<64>::new();
+        let a = builder.u8(5);
+        let mut b = builder.u8(7);
+
+        a.copy_into(&mut b);
+        a.write();
+        b.write();
 
-        // x/y = (x-y)/y
+        let (_, _, _, output) = builder.run(b"").unwrap().into_inner();
+        assert_eq!(output, [5, 5]);
     }
 }
@@ -1,11 +1,22 @@
 //! A builder that implements very basic operations.
 
-use crate::compiler::Program;
+use super::source_map::SourceMap;
+use crate::compiler::{error::ParseError, Program};
+use std::panic::Location;
 
 #[derive(Debug)]
 /// A builder that implementes very basic operations.
 pub struct Builder {
     pub(super) source: String,
+
+    /// The operations which emitted each part of the source.
+    pub(super) source_map: SourceMap,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Builder {
@@ -13,11 +24,12 @@ impl Builder {
     pub fn new() -> Self {
         Self {
             source: String::new(),
+            source_map: SourceMap::new(),
         }
     }
 
     /// Compiles the source code of this builder, returning an error if the source is malformed.
-    pub fn compile(&self) -> Result<Program, &'static str> {
+    pub fn compile(&self) -> Result<Program, ParseError> {
         Program::new(&self.source)
     }
 
@@ -26,66 +38,110 @@ impl Builder {
         &self.source
     }
 
+    /// Gets the source map of this builder, which attributes each part of its source to the
+    /// operation which emitted it.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Runs an operation, recording the source it emits in the source map under a name.
+    #[track_caller]
+    fn operation(&mut self, name: &'static str, f: impl FnOnce(&mut Self)) {
+        self.source_map
+            .open(name, Location::caller(), self.source.len());
+
+        f(self);
+
+        self.source_map.close(self.source.len());
+    }
+
     /// Adds an increment command.
+    #[track_caller]
     pub fn inc(&mut self) {
-        self.source += "+";
+        self.operation("Builder::inc", |builder| {
+            builder.source += "+";
+        });
     }
 
     /// Adds several increment commands, or several decrement commands if `value > 128`.
+    #[track_caller]
     pub fn inc_by(&mut self, value: u8) {
-        if value > 128 {
-            for _ in 0..=255 - value {
-                self.source += "-";
-            }
-        } else {
-            for _ in 0..value {
-                self.source += "+";
+        self.operation("Builder::inc_by", |builder| {
+            if value > 128 {
+                for _ in 0..=255 - value {
+                    builder.source += "-";
+                }
+            } else {
+                for _ in 0..value {
+                    builder.source += "+";
+                }
             }
-        }
+        });
     }
 
     /// Adds a decrement command.
+    #[track_caller]
     pub fn dec(&mut self) {
-        self.source += "-";
+        self.operation("Builder::dec", |builder| {
+            builder.source += "-";
+        });
     }
 
     /// Adds several decrement commands, or several increment commands if `value > 128`.
+    #[track_caller]
     pub fn dec_by(&mut self, value: u8) {
-        if value > 128 {
-            for _ in 0..=255 - value {
-                self.source += "+";
-            }
-        } else {
-            for _ in 0..value {
-                self.source += "-";
+        self.operation("Builder::dec_by", |builder| {
+            if value > 128 {
+                for _ in 0..=255 - value {
+                    builder.source += "+";
+                }
+            } else {
+                for _ in 0..value {
+                    builder.source += "-";
+                }
             }
-        }
+        });
     }
 
     /// Adds a command to move the pointer left.
+    #[track_caller]
     pub fn shl(&mut self) {
-        self.source += "<";
+        self.operation("Builder::shl", |builder| {
+            builder.source += "<";
+        });
     }
 
     /// Adds a command to move the pointer right.
+    #[track_caller]
     pub fn shr(&mut self) {
-        self.source += ">";
+        self.operation("Builder::shr", |builder| {
+            builder.source += ">";
+        });
     }
 
     /// Adds a command to read input into the current cell.
+    #[track_caller]
     pub fn read(&mut self) {
-        self.source += ",";
+        self.operation("Builder::read", |builder| {
+            builder.source += ",";
+        });
     }
 
     /// Adds a command to write the current cell into output.
+    #[track_caller]
     pub fn write(&mut self) {
-        self.source += ".";
+        self.operation("Builder::write", |builder| {
+            builder.source += ".";
+        });
     }
 
     /// Repeats the commands inside while the current cell is nonzero.
+    #[track_caller]
     pub fn repeat(&mut self, f: impl FnOnce(&mut Self)) {
-        self.source.push('[');
-        f(self);
-        self.source.push(']');
+        self.operation("Builder::repeat", |builder| {
+            builder.source.push('[');
+            f(builder);
+            builder.source.push(']');
+        });
     }
 }
@@ -2,4 +2,5 @@
 
 pub mod allocator;
 pub mod core;
+pub mod source_map;
 pub mod tracking;
@@ -0,0 +1,218 @@
+//! Source maps which attribute each part of a builder's source to the operation which emitted it.
+
+use std::panic::Location;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An operation along with the part of the source it emitted.
+pub struct Mapping {
+    /// The byte offset in the source where the operation's output starts.
+    pub start: usize,
+
+    /// The byte offset in the source right after the operation's output.
+    pub end: usize,
+
+    /// The name of the operation, such as `CellU8::copy_into`.
+    pub operation: &'static str,
+
+    /// Where the operation was called from. For operations called by other operations, this is
+    /// often somewhere inside the builder.
+    pub caller: &'static Location<'static>,
+
+    /// The number of operations this one was called by.
+    pub depth: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+/// A map from parts of a builder's source to the operations which emitted them.
+///
+/// Operations called by other operations are recorded too, so a part of the source can belong to
+/// several operations, such as a `TrackingBuilder::goto` inside a `CellU8::copy_into` inside a
+/// `CellU8::mul_assign`. Operations which emit nothing aren't recorded, and consecutive calls to
+/// the same operation from the same place are merged, so moving the pointer by ten cells is a
+/// single mapping rather than ten.
+pub struct SourceMap {
+    /// Every operation, ordered by where their output starts, with callers before the operations
+    /// they call.
+    mappings: Vec<Mapping>,
+
+    /// The indices of the operations which are still emitting, from the outermost to the innermost.
+    open: Vec<usize>,
+}
+
+impl SourceMap {
+    /// Constructs an empty source map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets every operation, ordered by where their output starts, with callers before the
+    /// operations they call.
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Gets the operations whose output contains a byte offset, from the outermost to the
+    /// innermost.
+    pub fn at(&self, offset: usize) -> Vec<&Mapping> {
+        let mut found = Vec::new();
+        let mut depth = usize::MAX;
+
+        let end = self
+            .mappings
+            .partition_point(|mapping| mapping.start <= offset);
+
+        // Walking backwards from the last operation to start at or before the offset, the first
+        // operation with a smaller depth than the last one found is always the one which called
+        // it. Anything containing the offset is one of these.
+        for mapping in self.mappings[..end].iter().rev() {
+            if mapping.depth < depth {
+                depth = mapping.depth;
+
+                if offset < mapping.end {
+                    found.push(mapping);
+                }
+
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+
+        found.reverse();
+        found
+    }
+
+    /// Gets the innermost operation whose output contains a byte offset.
+    pub fn innermost(&self, offset: usize) -> Option<&Mapping> {
+        self.at(offset).pop()
+    }
+
+    /// Gets the outermost operation whose output contains a byte offset, which is the one called
+    /// directly by code using the builder.
+    pub fn outermost(&self, offset: usize) -> Option<&Mapping> {
+        self.at(offset).first().copied()
+    }
+
+    /// Starts recording an operation whose output starts at a byte offset.
+    pub(super) fn open(
+        &mut self,
+        operation: &'static str,
+        caller: &'static Location<'static>,
+        start: usize,
+    ) {
+        self.open.push(self.mappings.len());
+        self.mappings.push(Mapping {
+            start,
+            end: start,
+            operation,
+            caller,
+            depth: self.open.len() - 1,
+        });
+    }
+
+    /// Finishes recording the innermost operation, whose output ends at a byte offset.
+    pub(super) fn close(&mut self, end: usize) {
+        let index = self.open.pop().expect("an operation is being recorded");
+        self.mappings[index].end = end;
+
+        let mapping = self.mappings[index];
+
+        // Operations which emit nothing can't have called anything which emitted something, so
+        // they are always the last mapping.
+        if mapping.start == end {
+            self.mappings.pop();
+            return;
+        }
+
+        // A previous mapping right before this one at the same depth can't have any mappings
+        // inside it, and neither can this one if it is the last mapping.
+        if index + 1 == self.mappings.len() && index > 0 {
+            let previous = &mut self.mappings[index - 1];
+
+            if previous.operation == mapping.operation
+                && previous.caller == mapping.caller
+                && previous.depth == mapping.depth
+                && previous.end == mapping.start
+            {
+                previous.end = end;
+                self.mappings.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::allocator::core::AllocatingBuilder;
+
+    #[test]
+    fn nested_operations_are_attributed_to_their_callers() {
+        let builder = AllocatingBuilder::<16>::new();
+        let mut product = builder.u8(3);
+        let factor = builder.u8(4);
+
+        let line = line!() + 1;
+        product *= &factor;
+
+        let map = builder.source_map();
+        let outer = *map
+            .mappings()
+            .iter()
+            .find(|mapping| mapping.operation == "CellU8::mul_assign")
+            .unwrap();
+
+        // Operations called by code using the builder point at that code, rather than at the
+        // builder's own code which calls its operations.
+        assert_eq!(outer.depth, 0);
+        assert_eq!(outer.caller.file(), file!());
+        assert_eq!(outer.caller.line(), line);
+
+        let mut deepest = 0;
+
+        for offset in outer.start..outer.end {
+            let found = map.at(offset);
+            assert_eq!(*found[0], outer);
+            assert_eq!(map.outermost(offset), Some(&outer));
+            assert_eq!(map.innermost(offset), found.last().copied());
+
+            for (depth, pair) in found.windows(2).enumerate() {
+                let (parent, child) = (pair[0], pair[1]);
+
+                assert_eq!(child.depth, depth + 1);
+                assert!(parent.start <= child.start && child.end <= parent.end);
+                assert!(child.start <= offset && offset < child.end);
+                assert_ne!(child.caller.file(), file!());
+            }
+
+            deepest = deepest.max(found.len());
+        }
+
+        // Multiplying adds the factor in a loop, which copies it through a temporary cell.
+        assert!(deepest >= 3);
+    }
+
+    #[test]
+    fn operations_called_one_after_another_are_separate() {
+        let builder = AllocatingBuilder::<16>::new();
+        let mut cell = builder.u8(0);
+
+        let first = line!() + 1;
+        cell.inc_by(3);
+        let second = line!() + 1;
+        cell.inc_by(2);
+
+        let map = builder.source_map();
+        let lines: Vec<u32> = map
+            .mappings()
+            .iter()
+            .filter(|mapping| mapping.operation == "CellU8::inc_by")
+            .map(|mapping| mapping.caller.line())
+            .collect();
+
+        assert_eq!(lines, [first, second]);
+        assert!(map
+            .mappings()
+            .iter()
+            .all(|mapping| mapping.start < mapping.end));
+    }
+}
@@ -1,7 +1,11 @@
 //! A builder that tracks the current pointer location, allowing the use of `goto` commands.
 
-use super::core::Builder;
-use crate::{compiler::Program, runner::Runner};
+use super::{core::Builder, source_map::SourceMap};
+use crate::{
+    compiler::{error::ParseError, execution::Outcome, Program},
+    runner::{Runner, RunnerOptions},
+};
+use std::panic::Location;
 
 #[derive(Debug)]
 /// A builder that tracks the current pointer location, allowing the use of `goto` commands.
@@ -10,6 +14,12 @@ pub struct TrackingBuilder<const N: usize> {
     index: usize,
 }
 
+impl<const N: usize> Default for TrackingBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const N: usize> TrackingBuilder<N> {
     /// Constructs a new `TrackingBuilder`.
     pub fn new() -> Self {
@@ -20,18 +30,29 @@ impl<const N: usize> TrackingBuilder<N> {
     }
 
     /// Compiles the source code of this builder, returning an error if the source is malformed.
-    pub fn compile(&self) -> Result<Program, &'static str> {
+    pub fn compile(&self) -> Result<Program, ParseError> {
         self.builder.compile()
     }
 
     /// Runs the source code in this builder, returning an error if the source is malformed.
-    pub fn run(&self, input: &[u8]) -> Result<Runner<N>, &'static str> {
-        self.compile().map(|program| program.run::<N>(input))
+    pub fn run(&self, input: &[u8]) -> Result<Runner<N>, ParseError> {
+        self.compile().map(|program| program.run::<N, u8>(input))
+    }
+
+    /// Runs the source code in this builder using custom runner options, returning an error if the
+    /// source is malformed. Otherwise, returns why the program stopped along with the runner.
+    pub fn run_with_options(
+        &self,
+        input: &[u8],
+        options: RunnerOptions,
+    ) -> Result<Outcome<N>, ParseError> {
+        self.compile()
+            .map(|program| program.run_with_options::<N, u8>(input, options))
     }
 
     /// Gets the source code of this builder.
     pub fn source(&self) -> &str {
-        &self.builder.source()
+        self.builder.source()
     }
 
     /// Gets a mutable ref to the source code of this builder.
@@ -39,90 +60,169 @@ impl<const N: usize> TrackingBuilder<N> {
         &mut self.builder.source
     }
 
+    /// Gets the source map of this builder, which attributes each part of its source to the
+    /// operation which emitted it.
+    pub fn source_map(&self) -> &SourceMap {
+        self.builder.source_map()
+    }
+
+    /// Gets a mutable ref to the source map of this builder.
+    pub(super) fn source_map_mut(&mut self) -> &mut SourceMap {
+        &mut self.builder.source_map
+    }
+
+    /// Runs an operation, recording the source it emits in the source map under a name.
+    #[track_caller]
+    fn operation(&mut self, name: &'static str, f: impl FnOnce(&mut Self)) {
+        let start = self.builder.source.len();
+        self.builder
+            .source_map
+            .open(name, Location::caller(), start);
+
+        f(self);
+
+        let end = self.builder.source.len();
+        self.builder.source_map.close(end);
+    }
+
     /// Gets the currently pointed at memory index.
     pub fn index(&self) -> usize {
         self.index
     }
 
     /// Adds an increment command.
+    #[track_caller]
     pub fn inc(&mut self) {
         self.builder.inc();
     }
 
     /// Adds several increment commands, or several decrement commands if `value > 128`.
+    #[track_caller]
     pub fn inc_by(&mut self, value: u8) {
         self.builder.inc_by(value);
     }
 
     /// Adds a decrement command.
+    #[track_caller]
     pub fn dec(&mut self) {
         self.builder.dec();
     }
 
     /// Adds several decrement commands, or several increment commands if `value > 128`.
+    #[track_caller]
     pub fn dec_by(&mut self, value: u8) {
         self.builder.dec_by(value);
     }
 
     /// Sets the current cell to zero.
+    #[track_caller]
     pub fn zero(&mut self) {
-        self.builder.repeat(|builder| builder.dec());
+        self.operation("TrackingBuilder::zero", |builder| {
+            builder.builder.repeat(|builder| builder.dec());
+        });
     }
 
     /// Sets the current cell to a given value.
+    #[track_caller]
     pub fn set(&mut self, value: u8) {
-        self.zero();
-        self.inc_by(value);
+        self.operation("TrackingBuilder::set", |builder| {
+            builder.zero();
+            builder.inc_by(value);
+        });
     }
 
     /// Adds several pointer shift commands to move the pointer to a given position.
+    #[track_caller]
     pub fn goto(&mut self, index: usize) {
-        if index >= N {
-            panic!("pointer index cannot be larger than N");
-        }
-
-        if index < self.index {
-            for _ in 0..self.index - index {
-                self.builder.shl();
+        self.operation("TrackingBuilder::goto", |builder| {
+            if index >= N {
+                panic!("pointer index cannot be larger than N");
             }
-        } else if index > self.index {
-            for _ in 0..index - self.index {
-                self.builder.shr();
+
+            if index < builder.index {
+                for _ in 0..builder.index - index {
+                    builder.builder.shl();
+                }
+            } else if index > builder.index {
+                for _ in 0..index - builder.index {
+                    builder.builder.shr();
+                }
             }
-        }
 
-        self.index = index;
+            builder.index = index;
+        });
     }
 
     /// Adds a command to read input into the current cell.
+    #[track_caller]
     pub fn read(&mut self) {
         self.builder.read();
     }
 
     /// Adds a command to write the current cell into output.
+    #[track_caller]
     pub fn write(&mut self) {
         self.builder.write();
     }
 
     /// Repeats the commands inside while the current cell is nonzero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the commands inside leave the pointer somewhere other than where they found it.
+    /// Programs can move the pointer in loops, but the builder couldn't track where the pointer is
+    /// afterwards, since that depends on how many times the loop runs. Use `repeat_at` to return
+    /// to a known cell at the end of each iteration instead.
+    #[track_caller]
     pub fn repeat(&mut self, f: impl FnOnce(&mut Self)) {
-        let old_index = self.index;
+        self.operation("TrackingBuilder::repeat", |builder| {
+            let old_index = builder.index;
 
-        self.builder.source.push('[');
-        f(self);
-        self.builder.source.push(']');
+            builder.builder.source.push('[');
+            f(builder);
+            builder.builder.source.push(']');
 
-        if self.index != old_index {
-            panic!("the pointer index unexpectedly changed in a [...] loop");
-        }
+            if builder.index != old_index {
+                panic!("the pointer index unexpectedly changed in a [...] loop");
+            }
+        });
     }
 
     /// Repeats the commands inside while the cell at `location` is nonzero. Guarantees that the pointer is at cell `location` at the beginning of each loop iteration.
+    #[track_caller]
     pub fn repeat_at(&mut self, location: usize, f: impl FnOnce(&mut Self)) {
-        self.goto(location);
-        self.builder.source.push('[');
-        f(self);
-        self.goto(location);
-        self.builder.source.push(']');
+        self.operation("TrackingBuilder::repeat_at", |builder| {
+            builder.goto(location);
+            builder.builder.source.push('[');
+            f(builder);
+            builder.goto(location);
+            builder.builder.source.push(']');
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackingBuilder;
+
+    #[test]
+    #[should_panic(expected = "the pointer index unexpectedly changed in a [...] loop")]
+    fn repeat_rejects_unbalanced_loops() {
+        let mut builder = TrackingBuilder::<4>::new();
+        builder.repeat(|builder| builder.goto(1));
+    }
+
+    #[test]
+    fn repeat_at_returns_to_its_cell() {
+        let mut builder = TrackingBuilder::<4>::new();
+        builder.set(3);
+        builder.repeat_at(0, |builder| {
+            builder.dec();
+            builder.goto(2);
+            builder.inc_by(2);
+        });
+
+        assert_eq!(builder.index(), 0);
+        assert_eq!(builder.run(b"").unwrap().cells(), [0, 0, 6, 0]);
     }
 }
@@ -0,0 +1,341 @@
+//! Static analysis which classifies every loop in a program without running it.
+
+use super::{
+    ast::Visitor,
+    error::{Location, ParseError, Span},
+    Instruction, Node, Program,
+};
+use std::fmt::{self, Display};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// What is known about a single loop without running it.
+pub struct LoopAnalysis {
+    /// The location of the loop's `[`.
+    pub location: Location,
+
+    /// How far each iteration moves the pointer, or `None` if it depends on the tape, which is the
+    /// case for loops containing unbalanced loops.
+    pub offset: Option<isize>,
+
+    /// Whether the current cell is always zero when the loop is reached, so it never runs. That is
+    /// the case for loops right after another loop, and for loops before the program writes to
+    /// any cell.
+    pub dead: bool,
+
+    /// Whether the loop never ends once entered, because its body returns the pointer to where it
+    /// started without changing the current cell, such as `[]`, `[+-]` or `[>+<]`.
+    pub infinite: bool,
+}
+
+impl LoopAnalysis {
+    /// Checks if each iteration of the loop leaves the pointer where it found it.
+    pub fn balanced(&self) -> bool {
+        self.offset == Some(0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// A problem found by `lint`.
+pub enum Lint {
+    /// The loop never runs, as described in `LoopAnalysis::dead`.
+    Dead,
+
+    /// The loop never ends once entered, as described in `LoopAnalysis::infinite`.
+    Infinite,
+
+    /// Each iteration of the loop moves the pointer by a fixed offset. This includes scans such as
+    /// `[>]`, which are often meant to, but builders only ever emit balanced loops.
+    Unbalanced(isize),
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// A problem found by `lint`, along with the loop it was found in.
+pub struct Diagnostic {
+    /// The problem which was found.
+    pub lint: Lint,
+
+    /// The source of the loop, from its `[` to its `]`.
+    pub span: Span,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lint {
+            Lint::Dead => write!(
+                f,
+                "{}: loop never runs, since the current cell is always zero when it is reached",
+                self.span.start
+            ),
+
+            Lint::Infinite => write!(
+                f,
+                "{}: loop never ends once entered, since it never changes the current cell",
+                self.span.start
+            ),
+
+            Lint::Unbalanced(offset) => write!(
+                f,
+                "{}: loop moves the pointer by {offset} each iteration",
+                self.span.start
+            ),
+        }
+    }
+}
+
+/// Analyzes source, returning a diagnostic for each loop which is dead, infinite or unbalanced in
+/// the order the loops appear. Loops which are dead aren't reported as anything else, since they
+/// never run. Returns an error if the source is malformed.
+pub fn lint(source: &str) -> Result<Vec<Diagnostic>, ParseError> {
+    let program = Program::new(source)?;
+
+    let mut spans = Spans(Vec::new());
+    program.walk(&mut spans);
+
+    let analyses = program.analyze();
+    debug_assert_eq!(
+        analyses.len(),
+        spans.0.len(),
+        "every loop is both analyzed and visited"
+    );
+
+    Ok(analyses
+        .into_iter()
+        .zip(spans.0)
+        .filter_map(|(analysis, span)| {
+            let lint = if analysis.dead {
+                Lint::Dead
+            } else if analysis.infinite {
+                Lint::Infinite
+            } else {
+                match analysis.offset {
+                    Some(offset) if offset != 0 => Lint::Unbalanced(offset),
+                    _ => return None,
+                }
+            };
+
+            Some(Diagnostic { lint, span })
+        })
+        .collect())
+}
+
+/// Collects the span of every loop, including the ones replaced by a single instruction, in the
+/// order they appear.
+struct Spans(Vec<Span>);
+
+impl Visitor for Spans {
+    fn visit(&mut self, node: &Node) {
+        if let Instruction::Loop(_)
+        | Instruction::Clear
+        | Instruction::MulAdd(_)
+        | Instruction::Scan(_) = node.instruction
+        {
+            self.0.push(node.span);
+        }
+    }
+}
+
+impl Program {
+    /// Classifies every loop in the program, in the order the loops appear in its source. Loops
+    /// which were replaced by a single operation, such as `[-]` or `[>]`, are included too.
+    pub fn analyze(&self) -> Vec<LoopAnalysis> {
+        let mut analyses = Vec::new();
+
+        // Whether no cell has been changed yet, in which case every cell is zero.
+        let mut pristine = true;
+
+        // Each entry holds the remaining instructions of a list, the index of the analysis of the
+        // loop the list belongs to, or `None` for the body of a procedure, along with whether the
+        // tape was pristine before it, the net offset of the list so far, and whether the current
+        // cell is known to be zero.
+        let mut stack = vec![(self.instructions.iter(), None, Some(0), true)];
+
+        loop {
+            let (remaining, _, offset, zero) =
+                stack.last_mut().expect("the stack is never empty here");
+
+            let Some(node) = remaining.next() else {
+                let (_, parent, offset, _) = stack.pop().expect("the stack is never empty here");
+
+                let Some((index, was_pristine)) = parent else {
+                    return analyses;
+                };
+
+                // Defining a procedure doesn't run it, so it can't have changed any cells yet.
+                let Some(index) = index else {
+                    pristine = was_pristine;
+                    continue;
+                };
+
+                let analysis: &mut LoopAnalysis = &mut analyses[index];
+                analysis.offset = offset;
+
+                // Nothing in a dead loop runs, so it can't have changed any cells.
+                if analysis.dead {
+                    pristine = was_pristine;
+                }
+
+                let (_, _, parent_offset, zero) =
+                    stack.last_mut().expect("the stack is never empty here");
+
+                if offset != Some(0) {
+                    *parent_offset = None;
+                }
+
+                *zero = true;
+                continue;
+            };
+
+            let dead = *zero;
+
+            let step = match &node.instruction {
+                Instruction::Add(_) | Instruction::Read => {
+                    pristine = false;
+                    *zero = false;
+                    continue;
+                }
+
+                Instruction::Move(step) => {
+                    *offset = offset.map(|offset| offset + step);
+                    *zero = pristine;
+                    continue;
+                }
+
+                Instruction::Write | Instruction::Dump => continue,
+
+                // A procedure can do anything, including moving the pointer by any amount.
+                Instruction::Call => {
+                    pristine = false;
+                    *zero = false;
+                    *offset = None;
+                    continue;
+                }
+
+                // Procedures can be called from anywhere, so nothing is known about the tape when
+                // their bodies start.
+                Instruction::Procedure(body) => {
+                    stack.push((body.iter(), Some((None, pristine)), Some(0), false));
+                    pristine = false;
+                    continue;
+                }
+
+                Instruction::Clear | Instruction::MulAdd(_) => 0,
+                Instruction::Scan(step) => *step,
+
+                Instruction::Loop(body) => {
+                    analyses.push(LoopAnalysis {
+                        location: node.span.start,
+                        offset: None,
+                        dead,
+                        infinite: infinite(body),
+                    });
+
+                    stack.push((
+                        body.iter(),
+                        Some((Some(analyses.len() - 1), pristine)),
+                        Some(0),
+                        false,
+                    ));
+
+                    // The current cell is nonzero inside a loop, so the tape isn't pristine.
+                    pristine = false;
+                    continue;
+                }
+            };
+
+            analyses.push(LoopAnalysis {
+                location: node.span.start,
+                offset: Some(step),
+                dead,
+                infinite: false,
+            });
+
+            if step != 0 {
+                *offset = None;
+            }
+
+            if !dead {
+                pristine = false;
+            }
+
+            *zero = true;
+        }
+    }
+}
+
+/// Checks if a loop body returns the pointer to where it started without changing the current
+/// cell, no matter how wide cells are.
+fn infinite(body: &[Node]) -> bool {
+    let mut position = 0;
+    let mut change = 0i32;
+
+    for node in body {
+        match &node.instruction {
+            Instruction::Add(value) if position == 0 => change = change.wrapping_add(*value),
+            Instruction::Add(_) | Instruction::Write | Instruction::Dump => {}
+            Instruction::Move(offset) => position += offset,
+            _ => return false,
+        }
+    }
+
+    position == 0 && change == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Lint};
+
+    /// Lints source, returning each problem along with the source of its loop.
+    fn problems(source: &str) -> Vec<(Lint, &str)> {
+        lint(source)
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span;
+                (diagnostic.lint, &source[span.start.offset..span.end.offset])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dead_loops_are_found() {
+        assert_eq!(
+            problems("[comment] +[-] [.] , [>]"),
+            [
+                (Lint::Dead, "[comment]"),
+                (Lint::Dead, "[.]"),
+                (Lint::Unbalanced(1), "[>]"),
+            ]
+        );
+
+        // Dead loops aren't reported as anything else, even if they would be infinite.
+        assert_eq!(problems(">[]"), [(Lint::Dead, "[]")]);
+    }
+
+    #[test]
+    fn infinite_loops_are_found() {
+        for source in ["+[]", "+[+-]", "+[>+<]", "+[.]"] {
+            assert_eq!(problems(source), [(Lint::Infinite, &source[1..])]);
+        }
+
+        assert_eq!(problems("+[-]+[->+<]+[>+<-]"), []);
+    }
+
+    #[test]
+    fn unbalanced_loops_are_found() {
+        assert_eq!(problems("+[>+>]"), [(Lint::Unbalanced(2), "[>+>]")]);
+        assert_eq!(problems("+[<<]"), [(Lint::Unbalanced(-2), "[<<]")]);
+
+        // Loops containing unbalanced loops move by an amount which depends on the tape.
+        assert_eq!(problems("+[[>]+]"), [(Lint::Unbalanced(1), "[>]")]);
+    }
+
+    #[test]
+    fn diagnostics_point_at_their_loops() {
+        let diagnostics = lint("+\n [>]").unwrap();
+
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "2:2: loop moves the pointer by 1 each iteration"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! The syntax tree of a parsed program, along with traits for walking and rewriting it.
+//!
+//! Runs of `+`/`-` and `<`/`>` are folded into single `Add` and `Move` instructions, so inverse
+//! commands cancel each other out, and common loop shapes are replaced by single instructions as
+//! described in the `optimizer` module. Every node keeps the span of source it came from.
+
+use super::{error::Span, optimizer::optimize, push, Program};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// An instruction in a program.
+pub enum Instruction {
+    /// Adds a value to the current cell, wrapping on overflow.
+    Add(i32),
+    /// Moves the pointer by an offset, wrapping around the ends of the tape.
+    Move(isize),
+    /// Reads a value from input into the current cell.
+    Read,
+    /// Writes the current cell into output.
+    Write,
+    /// Repeats the inner instructions while the current cell is nonzero.
+    Loop(Vec<Node>),
+    /// Sets the current cell to zero. Equivalent to `[-]`.
+    Clear,
+    /// For each `(offset, factor)` pair, adds the current cell times `factor` to the cell at
+    /// `offset`, then sets the current cell to zero. Equivalent to loops such as `[->+>++<<]`.
+    MulAdd(Vec<(isize, i32)>),
+    /// Moves the pointer by a step until it reaches a zero cell. Equivalent to `[>]` or `[<]`.
+    Scan(isize),
+    /// Records a snapshot of the tape. Only parsed with the `dump` option.
+    Dump,
+    /// Defines a procedure with the inner instructions, identified by the value of the current
+    /// cell, without running it. Only parsed with the `procedures` option.
+    Procedure(Vec<Node>),
+    /// Runs the procedure identified by the value of the current cell. Only parsed with the
+    /// `procedures` option.
+    Call,
+}
+
+impl Instruction {
+    /// Gets the inner instructions of a loop or procedure.
+    pub fn body(&self) -> Option<&[Node]> {
+        match self {
+            Instruction::Loop(body) | Instruction::Procedure(body) => Some(body),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// An instruction along with the span of source it came from. The span of a loop, or of an
+/// instruction which replaced one, runs from its `[` to right after its `]`, and likewise for
+/// procedures.
+pub struct Node {
+    /// The instruction.
+    pub instruction: Instruction,
+
+    /// The span of source the instruction came from.
+    pub span: Span,
+}
+
+/// Walks a syntax tree in source order without changing it.
+pub trait Visitor {
+    /// Visits a node, before the body of a loop.
+    fn visit(&mut self, node: &Node) {
+        let _ = node;
+    }
+
+    /// Visits a loop or procedure again, right after its body.
+    fn leave(&mut self, node: &Node) {
+        let _ = node;
+    }
+}
+
+/// Rewrites a syntax tree from the innermost loops outwards.
+pub trait Fold {
+    /// Rewrites a node, whose body has already been rewritten if it has one, by pushing any
+    /// number of nodes to replace it with onto `output`. By default, the node is kept as is.
+    fn fold(&mut self, node: Node, output: &mut Vec<Node>) {
+        output.push(node);
+    }
+}
+
+/// Walks a list of nodes with a visitor. This uses an explicit stack, so deeply nested loops can't
+/// overflow the native stack.
+pub fn walk(nodes: &[Node], visitor: &mut impl Visitor) {
+    // Each entry holds the remaining nodes of a list and the node the list is the body of.
+    let mut stack = vec![(nodes.iter(), None)];
+
+    while let Some((remaining, parent)) = stack.last_mut() {
+        let Some(node) = remaining.next() else {
+            if let Some(parent) = *parent {
+                visitor.leave(parent);
+            }
+
+            stack.pop();
+            continue;
+        };
+
+        visitor.visit(node);
+
+        if let Some(body) = node.instruction.body() {
+            stack.push((body.iter(), Some(node)));
+        }
+    }
+}
+
+/// Rewrites a list of nodes with a folder. This uses an explicit stack, so deeply nested loops
+/// can't overflow the native stack.
+pub fn fold(nodes: Vec<Node>, folder: &mut impl Fold) -> Vec<Node> {
+    // Each entry holds the remaining nodes of a list, the already rewritten ones, and the span of
+    // the node the list is the body of along with whether that node is a procedure.
+    let mut stack = vec![(nodes.into_iter(), Vec::new(), None)];
+
+    loop {
+        let (remaining, output, _) = stack.last_mut().expect("the stack is never empty here");
+
+        match remaining.next() {
+            Some(Node {
+                instruction: Instruction::Loop(body),
+                span,
+            }) => stack.push((body.into_iter(), Vec::new(), Some((span, false)))),
+
+            Some(Node {
+                instruction: Instruction::Procedure(body),
+                span,
+            }) => stack.push((body.into_iter(), Vec::new(), Some((span, true)))),
+
+            Some(node) => folder.fold(node, output),
+
+            None => {
+                let (_, body, parent) = stack.pop().expect("the stack is never empty here");
+
+                match (stack.last_mut(), parent) {
+                    (Some((_, output, _)), Some((span, procedure))) => {
+                        let instruction = if procedure {
+                            Instruction::Procedure(body)
+                        } else {
+                            Instruction::Loop(body)
+                        };
+
+                        folder.fold(Node { instruction, span }, output);
+                    }
+
+                    _ => return body,
+                }
+            }
+        }
+    }
+}
+
+impl Program {
+    /// Constructs a program from a syntax tree, without any embedded input. Adjacent `Add` and
+    /// `Move` instructions are folded together like they are when parsing, and loops with a known
+    /// shape are replaced by single instructions.
+    pub fn from_nodes(nodes: Vec<Node>) -> Self {
+        /// Pushes every node onto its list the same way the parser does.
+        struct Normalize;
+
+        impl Fold for Normalize {
+            fn fold(&mut self, node: Node, output: &mut Vec<Node>) {
+                push(output, node.instruction, node.span);
+            }
+        }
+
+        Program {
+            instructions: optimize(fold(nodes, &mut Normalize)),
+            input: Vec::new(),
+        }
+    }
+
+    /// Gets the syntax tree of this program.
+    pub fn nodes(&self) -> &[Node] {
+        &self.instructions
+    }
+
+    /// Takes the syntax tree of this program.
+    pub fn into_nodes(self) -> Vec<Node> {
+        self.instructions
+    }
+
+    /// Walks the syntax tree of this program with a visitor.
+    pub fn walk(&self, visitor: &mut impl Visitor) {
+        walk(&self.instructions, visitor);
+    }
+
+    /// Rewrites the syntax tree of this program with a folder, then normalizes it like
+    /// `Program::from_nodes`. The input embedded in the program is kept.
+    pub fn fold(self, folder: &mut impl Fold) -> Self {
+        Program {
+            input: self.input,
+            ..Self::from_nodes(fold(self.instructions, folder))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fold, Instruction, Node, Visitor};
+    use crate::compiler::{ParseOptions, Program};
+
+    /// Records the instructions which are visited and left, without their bodies.
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl Visitor for Trace {
+        fn visit(&mut self, node: &Node) {
+            self.0.push(match node.instruction {
+                Instruction::Loop(_) => "[".to_string(),
+                Instruction::Procedure(_) => "(".to_string(),
+                _ => format!("{:?}", node.instruction),
+            });
+        }
+
+        fn leave(&mut self, node: &Node) {
+            self.0.push(match node.instruction {
+                Instruction::Procedure(_) => ")".to_string(),
+                _ => "]".to_string(),
+            });
+        }
+    }
+
+    /// Removes every `Write`.
+    struct Silence;
+
+    impl Fold for Silence {
+        fn fold(&mut self, node: Node, output: &mut Vec<Node>) {
+            if node.instruction != Instruction::Write {
+                output.push(node);
+            }
+        }
+    }
+
+    #[test]
+    fn visitors_see_bodies_between_visiting_and_leaving() {
+        let options = ParseOptions {
+            procedures: true,
+            ..ParseOptions::default()
+        };
+
+        let program = Program::with_options("+[>[.]<](,)", options).unwrap();
+        let mut trace = Trace::default();
+        program.walk(&mut trace);
+
+        assert_eq!(
+            trace.0,
+            ["Add(1)", "[", "Move(1)", "[", "Write", "]", "Move(-1)", "]", "(", "Read", ")"]
+        );
+    }
+
+    #[test]
+    fn deeply_nested_loops_can_be_walked_and_folded() {
+        let depth = 10_000;
+        let source = "[".repeat(depth) + "." + &"]".repeat(depth);
+        let program = Program::new(&source).unwrap();
+
+        let mut trace = Trace::default();
+        program.walk(&mut trace);
+        assert_eq!(trace.0.len(), depth * 2 + 1);
+
+        let folded = program.fold(&mut Silence);
+        let mut trace = Trace::default();
+        folded.walk(&mut trace);
+        assert_eq!(trace.0.len(), depth * 2);
+    }
+
+    #[test]
+    fn folding_normalizes_the_result() {
+        let options = ParseOptions {
+            embedded_input: true,
+            ..ParseOptions::default()
+        };
+
+        // With the writes gone, the runs on either side are folded together, and the loop is
+        // recognized as a clear.
+        let program = Program::with_options("++.-[-.]>.>!input", options).unwrap();
+        let folded = program.fold(&mut Silence);
+        let instructions: Vec<_> = folded
+            .nodes()
+            .iter()
+            .map(|node| node.instruction.clone())
+            .collect();
+
+        assert_eq!(
+            instructions,
+            [
+                Instruction::Add(1),
+                Instruction::Clear,
+                Instruction::Move(2)
+            ]
+        );
+        assert_eq!(folded.input(), b"input");
+    }
+}
@@ -0,0 +1,165 @@
+//! Translates programs into standalone C source files.
+
+use super::{CellWidth, TargetOptions, TranslateError};
+use crate::{
+    compiler::{bytecode::Op, Program},
+    runner::Eof,
+};
+use std::fmt::Write;
+
+/// Translates a program into a C99 source file which reads from stdin and writes to stdout.
+///
+/// The generated code follows the program's bytecode, so folded runs and recognised loop idioms
+/// become single statements. Its output matches `Program::run` with the same tape size, cell width
+/// and EOF convention byte for byte, except that input embedded in the program isn't included
+/// and dumps are ignored.
+///
+/// Returns `TranslateError::UnsupportedInstruction` if the program defines or calls procedures.
+pub fn transpile(program: &Program, options: TargetOptions) -> Result<String, TranslateError> {
+    let cell = match options.cell {
+        CellWidth::U8 => "uint8_t",
+        CellWidth::U16 => "uint16_t",
+        CellWidth::U32 => "uint32_t",
+    };
+
+    let bytecode = program.bytecode();
+    let mut output = String::new();
+
+    output += "#include <stdint.h>\n";
+    output += "#include <stdio.h>\n\n";
+    writeln!(output, "#define TAPE_SIZE {}\n", options.tape_size).unwrap();
+    writeln!(output, "typedef {cell} cell;\n").unwrap();
+    output += "static cell tape[TAPE_SIZE];\n\n";
+    output += "int main(void) {\n";
+    output += "    size_t p = 0;\n";
+
+    if bytecode.ops().contains(&Op::Read) {
+        output += "    int c;\n";
+    }
+
+    output += "\n";
+
+    let mut depth = 1;
+
+    for (address, op) in bytecode.ops().iter().enumerate() {
+        if let Op::JumpIfNonzero(_) = op {
+            depth -= 1;
+        }
+
+        let indent = "    ".repeat(depth);
+
+        match op {
+            Op::Add(value) => {
+                writeln!(output, "{indent}tape[p] += {}u;", options.cell.wrap(*value)).unwrap()
+            }
+
+            Op::Move(offset) => {
+                if options.forward(*offset) != 0 {
+                    writeln!(output, "{indent}p = {};", index(&options, *offset)).unwrap();
+                }
+            }
+
+            Op::Read => {
+                writeln!(output, "{indent}c = getchar();").unwrap();
+
+                match options.eof {
+                    Eof::Zero => {
+                        writeln!(output, "{indent}tape[p] = c == EOF ? 0 : (cell)c;").unwrap()
+                    }
+                    Eof::MinusOne => {
+                        writeln!(output, "{indent}tape[p] = c == EOF ? (cell)-1 : (cell)c;")
+                            .unwrap()
+                    }
+                    Eof::Unchanged => {
+                        writeln!(output, "{indent}if (c != EOF) tape[p] = (cell)c;").unwrap()
+                    }
+                }
+            }
+
+            Op::Write => writeln!(output, "{indent}putchar((unsigned char)tape[p]);").unwrap(),
+
+            Op::Clear => writeln!(output, "{indent}tape[p] = 0;").unwrap(),
+
+            Op::MulAdd(targets) => {
+                // Multiplying in 32 bits avoids overflowing `int` after narrow cells are promoted.
+                for (offset, factor) in targets {
+                    writeln!(
+                        output,
+                        "{indent}tape[{}] += (uint32_t)tape[p] * {}u;",
+                        index(&options, *offset),
+                        options.cell.wrap(*factor),
+                    )
+                    .unwrap();
+                }
+
+                writeln!(output, "{indent}tape[p] = 0;").unwrap();
+            }
+
+            Op::Scan(step) => writeln!(
+                output,
+                "{indent}while (tape[p]) p = {};",
+                index(&options, *step)
+            )
+            .unwrap(),
+
+            Op::JumpIfZero(_) => {
+                writeln!(output, "{indent}while (tape[p]) {{").unwrap();
+                depth += 1;
+            }
+
+            Op::JumpIfNonzero(_) => writeln!(output, "{indent}}}").unwrap(),
+
+            Op::Dump => {}
+
+            Op::Define(_) | Op::Call | Op::Return => {
+                return Err(TranslateError::UnsupportedInstruction(
+                    bytecode.location(address),
+                ))
+            }
+        }
+    }
+
+    output += "\n    return 0;\n";
+    output += "}\n";
+
+    Ok(output)
+}
+
+/// Gets a C expression for the index of the cell at an offset from the pointer.
+fn index(options: &TargetOptions, offset: isize) -> String {
+    match options.forward(offset) {
+        0 => "p".to_string(),
+        offset => format!("(p + {offset}) % TAPE_SIZE"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::backend::tests::{build, run_executable, scratch_dir, CORPUS};
+    use std::process::Command;
+
+    #[test]
+    fn compiled_programs_match_the_interpreter() {
+        let dir = scratch_dir("c");
+
+        for case in CORPUS {
+            let source = dir.join(format!("{}.c", case.name));
+            let executable = dir.join(case.name);
+            std::fs::write(&source, transpile(&case.program(), case.target()).unwrap()).unwrap();
+
+            build(
+                Command::new("cc")
+                    .arg("-std=c99")
+                    .arg("-o")
+                    .arg(&executable)
+                    .arg(&source),
+            );
+
+            let output = run_executable(&executable, &[], case.input);
+            assert_eq!(output, case.expected(), "{}", case.name);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+//! Compiles programs into standalone x86-64 Linux executables.
+//!
+//! Executables are static ELF files with no dependencies. They talk to the kernel through raw
+//! `read`, `write` and `exit` syscalls, and keep their tape in `.bss`, so it costs nothing in the
+//! file itself.
+
+use super::{
+    x86_64::{Assembler, Environment},
+    TargetOptions, TranslateError,
+};
+use crate::{compiler::Program, runner::Eof};
+
+/// The address the executable is loaded at.
+const BASE: u64 = 0x400000;
+
+/// The size of a page, which segments are aligned to.
+const PAGE: u64 = 0x1000;
+
+const ELF_HEADER_SIZE: u64 = 64;
+const PROGRAM_HEADER_SIZE: u64 = 56;
+const SECTION_HEADER_SIZE: u64 = 64;
+
+/// The names of the sections, in the order of their headers after the null section.
+const SECTION_NAMES: &[u8] = b"\0.text\0.bss\0.shstrtab\0";
+
+/// Compiles a program into an x86-64 Linux executable which reads from stdin and writes to stdout.
+///
+/// Its output matches `Program::run` with the same tape size, cell width and EOF convention byte
+/// for byte, except that input embedded in the program isn't included and dumps are ignored. It
+/// exits with status 0 once the program completes, or 1 if reading or writing fails.
+///
+/// Returns `TranslateError::UnsupportedInstruction` if the program defines or calls procedures.
+///
+/// # Panics
+///
+/// Panics if the tape size is 0.
+pub fn compile(program: &Program, options: TargetOptions) -> Result<Vec<u8>, TranslateError> {
+    let size = options.cell.bits() as usize / 8;
+    let tape_bytes = (options.tape_size * size) as u64;
+
+    let text_offset = ELF_HEADER_SIZE + 2 * PROGRAM_HEADER_SIZE;
+    let entry = BASE + text_offset;
+
+    let assemble = |bss| {
+        let environment = Syscalls {
+            bss,
+            len: options.tape_size,
+            eof: options.eof,
+        };
+
+        Assembler::compile(&program.bytecode(), options.tape_size, size, &environment)
+    };
+
+    // The code holds the address of the tape, which comes after the code. The length of the code
+    // doesn't depend on that address, so assembling twice is enough to place the tape.
+    let bss = (entry + assemble(0)?.len() as u64).next_multiple_of(PAGE);
+    let code = assemble(bss)?;
+
+    let text_end = text_offset + code.len() as u64;
+    let names_offset = text_end;
+    let section_offset = names_offset + SECTION_NAMES.len() as u64;
+
+    let mut file = Vec::new();
+
+    // The ELF header: a 64-bit little-endian System V executable for x86-64.
+    file.extend_from_slice(b"\x7FELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+    file.extend_from_slice(&2u16.to_le_bytes());
+    file.extend_from_slice(&0x3Eu16.to_le_bytes());
+    file.extend_from_slice(&1u32.to_le_bytes());
+    file.extend_from_slice(&entry.to_le_bytes());
+    file.extend_from_slice(&ELF_HEADER_SIZE.to_le_bytes());
+    file.extend_from_slice(&section_offset.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    file.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    file.extend_from_slice(&2u16.to_le_bytes());
+    file.extend_from_slice(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+    file.extend_from_slice(&4u16.to_le_bytes());
+    file.extend_from_slice(&3u16.to_le_bytes());
+
+    // A readable and executable segment holding the headers and the code.
+    program_header(&mut file, 5, 0, BASE, text_end, text_end);
+
+    // A readable and writable segment for the tape, which is zeroed by the kernel.
+    program_header(&mut file, 6, 0, bss, 0, tape_bytes);
+
+    file.extend_from_slice(&code);
+    file.extend_from_slice(SECTION_NAMES);
+
+    // Section headers aren't needed to run the file, but let tools such as `objdump` read it.
+    file.extend_from_slice(&[0; SECTION_HEADER_SIZE as usize]);
+    section_header(&mut file, 1, 1, 6, entry, text_offset, code.len() as u64);
+    section_header(&mut file, 7, 8, 3, bss, text_end, tape_bytes);
+    section_header(
+        &mut file,
+        12,
+        3,
+        0,
+        0,
+        names_offset,
+        SECTION_NAMES.len() as u64,
+    );
+
+    Ok(file)
+}
+
+/// Compiles a program into an x86-64 Linux executable as described in `compile`, and saves it to
+/// a path with permission to execute it. A program which can't be compiled is reported as an
+/// error of kind `InvalidInput`.
+#[cfg(unix)]
+pub fn save(
+    program: &Program,
+    options: TargetOptions,
+    path: impl AsRef<std::path::Path>,
+) -> std::io::Result<()> {
+    use std::{
+        fs::{self, Permissions},
+        os::unix::fs::PermissionsExt,
+    };
+
+    let file = compile(program, options)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    fs::write(&path, file)?;
+    fs::set_permissions(path, Permissions::from_mode(0o755))
+}
+
+/// Appends a loadable program header.
+fn program_header(
+    file: &mut Vec<u8>,
+    flags: u32,
+    offset: u64,
+    address: u64,
+    size: u64,
+    memory: u64,
+) {
+    file.extend_from_slice(&1u32.to_le_bytes());
+    file.extend_from_slice(&flags.to_le_bytes());
+    file.extend_from_slice(&offset.to_le_bytes());
+    file.extend_from_slice(&address.to_le_bytes());
+    file.extend_from_slice(&address.to_le_bytes());
+    file.extend_from_slice(&size.to_le_bytes());
+    file.extend_from_slice(&memory.to_le_bytes());
+    file.extend_from_slice(&PAGE.to_le_bytes());
+}
+
+/// Appends a section header.
+fn section_header(
+    file: &mut Vec<u8>,
+    name: u32,
+    kind: u32,
+    flags: u64,
+    address: u64,
+    offset: u64,
+    size: u64,
+) {
+    file.extend_from_slice(&name.to_le_bytes());
+    file.extend_from_slice(&kind.to_le_bytes());
+    file.extend_from_slice(&flags.to_le_bytes());
+    file.extend_from_slice(&address.to_le_bytes());
+    file.extend_from_slice(&offset.to_le_bytes());
+    file.extend_from_slice(&size.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&1u64.to_le_bytes());
+    file.extend_from_slice(&0u64.to_le_bytes());
+}
+
+/// Runs compiled code as a whole process which performs input and output through syscalls. The
+/// exit status is kept in `r14`, and the byte being read is buffered on the stack.
+struct Syscalls {
+    /// The address of the tape.
+    bss: u64,
+
+    len: usize,
+    eof: Eof,
+}
+
+impl Syscalls {
+    /// Emits a syscall on the current cell, or the stack buffer for `read`, with a length of 1.
+    fn syscall(assembler: &mut Assembler, number: u8, descriptor: u8, buffer: &[u8]) {
+        // mov eax, number; mov edi, descriptor
+        assembler.emit(&[0xB8, number, 0, 0, 0, 0xBF, descriptor, 0, 0, 0]);
+        assembler.emit(buffer);
+
+        // mov edx, 1; syscall
+        assembler.emit(&[0xBA, 1, 0, 0, 0, 0x0F, 0x05]);
+    }
+
+    /// Emits code which leaves the program with an exit status of 1.
+    fn fail(assembler: &mut Assembler) {
+        // mov r14d, 1; jmp exit
+        assembler.emit(&[0x41, 0xBE, 1, 0, 0, 0]);
+        assembler.exit(&[0xE9]);
+    }
+}
+
+impl Environment for Syscalls {
+    fn prologue(&self, assembler: &mut Assembler) {
+        // mov rbx, bss; xor r12d, r12d; xor r14d, r14d; sub rsp, 8
+        assembler.emit(&[0x48, 0xBB]);
+        assembler.emit(&self.bss.to_le_bytes());
+        assembler.emit(&[0x45, 0x31, 0xE4, 0x45, 0x31, 0xF6, 0x48, 0x83, 0xEC, 0x08]);
+
+        // mov r13, len
+        assembler.emit(&[0x49, 0xBD]);
+        assembler.emit(&(self.len as u64).to_le_bytes());
+    }
+
+    fn read(&self, assembler: &mut Assembler) {
+        // read(0, rsp, 1), with mov rsi, rsp
+        Self::syscall(assembler, 0, 0, &[0x48, 0x89, 0xE6]);
+
+        // cmp rax, 1; jl missing
+        assembler.emit(&[0x48, 0x83, 0xF8, 0x01]);
+        let missing = assembler.jump(&[0x0F, 0x8C]);
+
+        // movzx eax, byte [rsp]; mov [current], eax; jmp done
+        assembler.emit(&[0x0F, 0xB6, 0x04, 0x24]);
+        assembler.store_current(0);
+        let done = assembler.jump(&[0xE9]);
+
+        // test rax, rax; jz eof
+        assembler.patch(missing, assembler.len());
+        assembler.emit(&[0x48, 0x85, 0xC0]);
+        let eof = assembler.jump(&[0x0F, 0x84]);
+        Self::fail(assembler);
+
+        assembler.patch(eof, assembler.len());
+
+        match self.eof {
+            Eof::Zero => assembler.clear(),
+            Eof::MinusOne => {
+                assembler.clear();
+                assembler.add(-1);
+            }
+            Eof::Unchanged => {}
+        }
+
+        assembler.patch(done, assembler.len());
+    }
+
+    fn write(&self, assembler: &mut Assembler) {
+        // write(1, current, 1), with lea rsi, [current]
+        let current = assembler.current();
+        Self::syscall(assembler, 1, 1, &[0x4A, 0x8D, 0x34, current]);
+
+        // test rax, rax; jns done
+        assembler.emit(&[0x48, 0x85, 0xC0]);
+        let done = assembler.jump(&[0x0F, 0x89]);
+        Self::fail(assembler);
+
+        assembler.patch(done, assembler.len());
+    }
+
+    fn epilogue(&self, assembler: &mut Assembler) {
+        // mov edi, r14d; mov eax, 60; syscall
+        assembler.emit(&[0x44, 0x89, 0xF7, 0xB8, 60, 0, 0, 0, 0x0F, 0x05]);
+    }
+}
+
+#[cfg(all(test, target_arch = "x86_64", target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::compiler::backend::tests::{run_executable, scratch_dir, CORPUS};
+
+    #[test]
+    fn executables_match_the_interpreter() {
+        let dir = scratch_dir("elf");
+
+        for case in CORPUS {
+            let executable = dir.join(case.name);
+            save(&case.program(), case.target(), &executable).unwrap();
+
+            let output = run_executable(&executable, &[], case.input);
+            assert_eq!(output, case.expected(), "{}", case.name);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+//! Backends which translate a program into other languages or executables, so it can run without a
+//! `Runner`.
+//!
+//! Every backend keeps the semantics of `Program::run` under the default `TapePolicy`: the pointer
+//! wraps around the ends of the tape, cell arithmetic wraps at the width of a cell, and output is
+//! the lowest byte of each written cell.
+
+pub mod c;
+pub mod elf;
+pub mod rust;
+pub mod wat;
+pub(crate) mod x86_64;
+
+use crate::{compiler::error::Location, runner::Eof};
+use std::{error::Error, fmt::Display};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// An error returned when a program can't be translated.
+pub enum TranslateError {
+    /// The name given to the generated code isn't a valid identifier in the target language.
+    InvalidName(String),
+
+    /// The program uses an instruction the target can't express, which is any that defines or
+    /// calls a procedure. The location is the first such instruction.
+    UnsupportedInstruction(Location),
+}
+
+impl Display for TranslateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranslateError::InvalidName(name) => write!(f, "`{name}` isn't a valid identifier"),
+
+            TranslateError::UnsupportedInstruction(location) => {
+                write!(f, "unsupported instruction at {location}")
+            }
+        }
+    }
+}
+
+impl Error for TranslateError {}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// The width of the cells on the tape of a translated program.
+pub enum CellWidth {
+    #[default]
+    /// 8-bit cells, like a `Runner` over `u8`.
+    U8,
+
+    /// 16-bit cells, like a `Runner` over `u16`.
+    U16,
+
+    /// 32-bit cells, like a `Runner` over `u32`.
+    U32,
+}
+
+impl CellWidth {
+    /// Gets the number of bits in a cell.
+    pub fn bits(self) -> u32 {
+        match self {
+            CellWidth::U8 => 8,
+            CellWidth::U16 => 16,
+            CellWidth::U32 => 32,
+        }
+    }
+
+    /// Wraps a signed amount to the width of a cell, giving the unsigned value with the same
+    /// effect when added to a cell.
+    pub fn wrap(self, value: i32) -> u32 {
+        match self {
+            CellWidth::U8 => value as u8 as u32,
+            CellWidth::U16 => value as u16 as u32,
+            CellWidth::U32 => value as u32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// Options which describe the machine a translated program runs on.
+pub struct TargetOptions {
+    /// The number of cells on the tape.
+    pub tape_size: usize,
+
+    /// The width of each cell.
+    pub cell: CellWidth,
+
+    /// What reading does once input is exhausted.
+    pub eof: Eof,
+}
+
+impl Default for TargetOptions {
+    fn default() -> Self {
+        Self {
+            tape_size: 30000,
+            cell: CellWidth::default(),
+            eof: Eof::default(),
+        }
+    }
+}
+
+impl TargetOptions {
+    /// Converts a pointer offset into the equivalent forward offset on a wrapping tape.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tape size is 0.
+    pub fn forward(&self, offset: isize) -> usize {
+        if self.tape_size == 0 {
+            panic!("cannot translate a program for a tape of size 0");
+        }
+
+        offset.rem_euclid(self.tape_size as isize) as usize
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        compiler::{execution::Limit, ParseOptions, Program},
+        runner::{cell::CellValue, RunnerOptions},
+    };
+    use std::{
+        io::Write,
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+    };
+
+    /// The number of cells on the tape in every case, which is small enough for cases to wrap
+    /// around it.
+    pub(crate) const TAPE_SIZE: usize = 64;
+
+    /// A program which every backend must run exactly like the interpreter.
+    pub(crate) struct Case {
+        /// A name for the case, which is also a valid identifier and file name.
+        pub(crate) name: &'static str,
+        pub(crate) source: &'static str,
+        pub(crate) input: &'static [u8],
+        pub(crate) cell: CellWidth,
+        pub(crate) eof: Eof,
+    }
+
+    impl Case {
+        const fn new(name: &'static str, source: &'static str, input: &'static [u8]) -> Self {
+            Self {
+                name,
+                source,
+                input,
+                cell: CellWidth::U8,
+                eof: Eof::Zero,
+            }
+        }
+
+        const fn cell(self, cell: CellWidth) -> Self {
+            Self { cell, ..self }
+        }
+
+        const fn eof(self, eof: Eof) -> Self {
+            Self { eof, ..self }
+        }
+
+        pub(crate) fn program(&self) -> Program {
+            Program::new(self.source).unwrap()
+        }
+
+        pub(crate) fn target(&self) -> TargetOptions {
+            TargetOptions {
+                tape_size: TAPE_SIZE,
+                cell: self.cell,
+                eof: self.eof,
+            }
+        }
+
+        pub(crate) fn runner_options(&self) -> RunnerOptions {
+            RunnerOptions {
+                eof: self.eof,
+                ..RunnerOptions::default()
+            }
+        }
+
+        /// Gets the output of the case under the interpreter.
+        pub(crate) fn expected(&self) -> Vec<u8> {
+            fn run<T: CellValue>(case: &Case) -> Vec<u8> {
+                let outcome = case.program().run_limited::<TAPE_SIZE, T>(
+                    case.input,
+                    case.runner_options(),
+                    Limit::default(),
+                );
+
+                assert!(outcome.halted(), "{} didn't halt", case.name);
+                outcome.runner.output().clone()
+            }
+
+            match self.cell {
+                CellWidth::U8 => run::<u8>(self),
+                CellWidth::U16 => run::<u16>(self),
+                CellWidth::U32 => run::<u32>(self),
+            }
+        }
+    }
+
+    const HELLO_WORLD: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+    /// Cells wrap below zero, and a multiply-add wraps past the largest value of a cell.
+    const WRAPPING_CELLS: &str = "-.+.-->-<[->+<]>.";
+
+    /// The pointer wraps off the left end of the tape, then scans back across it.
+    const WRAPPING_POINTER: &str = "+<-.<++.>>.[<]<.";
+
+    /// 256 only fits in cells wider than a byte, which `[>+<[-]]` tells apart.
+    const WIDE_CELLS: &str = "++++++++[>++++++++<-]>[>++++<-]>[>+<[-]]>.";
+
+    pub(crate) const CORPUS: &[Case] = &[
+        Case::new("hello_world", HELLO_WORLD, b""),
+        Case::new("hello_world_u16", HELLO_WORLD, b"").cell(CellWidth::U16),
+        Case::new("hello_world_u32", HELLO_WORLD, b"").cell(CellWidth::U32),
+        Case::new("wrapping_cells", WRAPPING_CELLS, b""),
+        Case::new("wrapping_cells_u16", WRAPPING_CELLS, b"").cell(CellWidth::U16),
+        Case::new("wrapping_cells_u32", WRAPPING_CELLS, b"").cell(CellWidth::U32),
+        Case::new("wrapping_pointer", WRAPPING_POINTER, b""),
+        Case::new("echo", ",[.,]", b"echo"),
+        Case::new("eof_zero", ",.,.,.", b"a"),
+        Case::new("eof_minus_one", ",.,.,.", b"a").eof(Eof::MinusOne),
+        Case::new("eof_unchanged", ",.,.,.", b"a").eof(Eof::Unchanged),
+        Case::new("eof_minus_one_u16", ",.,+.", b"a")
+            .cell(CellWidth::U16)
+            .eof(Eof::MinusOne),
+        Case::new("nested_loops", "++[>++[>++[>++<-]<-]<-]>>>.", b""),
+        Case::new("mul_add_backwards", ">>>>++++++[-<<+++<++>>>]<<.<.", b""),
+        Case::new("mul_add_off_the_left", "++++[-<+++>]<.", b""),
+        Case::new("wide_cells", WIDE_CELLS, b""),
+        Case::new("wide_cells_u16", WIDE_CELLS, b"").cell(CellWidth::U16),
+        Case::new("wide_cells_u32", WIDE_CELLS, b"").cell(CellWidth::U32),
+    ];
+
+    /// Creates an empty directory for a backend's test files.
+    pub(crate) fn scratch_dir(backend: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bf-{backend}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Runs a command which builds something, failing the test if it can't be run or fails.
+    pub(crate) fn build(command: &mut Command) {
+        let status = command
+            .status()
+            .unwrap_or_else(|error| panic!("couldn't run {command:?}: {error}"));
+
+        assert!(status.success(), "{command:?} failed");
+    }
+
+    /// Runs an executable on some input, returning what it wrote to stdout.
+    pub(crate) fn run_executable(path: &Path, args: &[&str], input: &[u8]) -> Vec<u8> {
+        let mut command = Command::new(path);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+
+        // A file this process just wrote can briefly be held open by a child forked by another
+        // test, in which case running it fails with `ETXTBSY` until that child calls `exec`.
+        let mut child = loop {
+            match command.spawn() {
+                Err(error) if error.raw_os_error() == Some(26) => {
+                    std::thread::sleep(std::time::Duration::from_millis(10))
+                }
+
+                result => break result.unwrap(),
+            }
+        };
+
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert!(output.status.success(), "{path:?} failed");
+        output.stdout
+    }
+
+    #[test]
+    fn procedures_are_unsupported() {
+        let options = ParseOptions {
+            procedures: true,
+            ..ParseOptions::default()
+        };
+
+        let program = Program::with_options("+(-)+:", options).unwrap();
+        let target = TargetOptions::default();
+        let error = TranslateError::UnsupportedInstruction(Location {
+            offset: 1,
+            line: 1,
+            column: 2,
+        });
+
+        assert_eq!(c::transpile(&program, target), Err(error.clone()));
+        assert_eq!(rust::transpile(&program, target, "run"), Err(error.clone()));
+        assert_eq!(wat::transpile(&program, target), Err(error.clone()));
+        assert_eq!(elf::compile(&program, target), Err(error));
+    }
+}
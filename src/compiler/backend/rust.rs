@@ -0,0 +1,276 @@
+//! Translates programs into Rust source code.
+
+use super::{CellWidth, TargetOptions, TranslateError};
+use crate::{
+    compiler::{bytecode::Op, Program},
+    runner::Eof,
+};
+use std::fmt::Write;
+
+/// Translates a program into a Rust function with the given name, which takes input bytes and
+/// returns output bytes. The function has no dependencies, so it can be pasted into any crate or
+/// pulled in with `include!` from a file generated by a build script.
+///
+/// The generated code follows the program's bytecode, so folded runs and recognised loop idioms
+/// become single statements. Its output matches `Program::run` with the same tape size, cell width
+/// and EOF convention byte for byte, except that input embedded in the program isn't included
+/// and dumps are ignored.
+///
+/// Returns `TranslateError::InvalidName` if the name isn't an ASCII Rust identifier, or is a
+/// keyword, and `TranslateError::UnsupportedInstruction` if the program defines or calls
+/// procedures.
+pub fn transpile(
+    program: &Program,
+    options: TargetOptions,
+    name: &str,
+) -> Result<String, TranslateError> {
+    check_name(name)?;
+
+    let mut output = String::new();
+    write_function(&mut output, program, options, name, "")?;
+    Ok(output)
+}
+
+/// Translates a program into a Rust module with the given name, which contains a single function
+/// `run` as described in `transpile`.
+///
+/// Returns an error under the same conditions as `transpile`.
+pub fn transpile_module(
+    program: &Program,
+    options: TargetOptions,
+    name: &str,
+) -> Result<String, TranslateError> {
+    check_name(name)?;
+
+    let mut output = String::new();
+
+    writeln!(output, "pub mod {name} {{").unwrap();
+    write_function(&mut output, program, options, "run", "    ")?;
+    output += "}\n";
+
+    Ok(output)
+}
+
+/// Words which can't be used as an item name, even though they're shaped like identifiers.
+const KEYWORDS: &[&str] = &[
+    "_", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Checks that a name can be used as is for a generated function or module.
+fn check_name(name: &str) -> Result<(), TranslateError> {
+    let mut chars = name.chars();
+
+    let valid = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name);
+
+    if valid {
+        Ok(())
+    } else {
+        Err(TranslateError::InvalidName(name.to_string()))
+    }
+}
+
+/// Writes a function which runs a program, with every line prefixed by an indent.
+fn write_function(
+    output: &mut String,
+    program: &Program,
+    options: TargetOptions,
+    name: &str,
+    indent: &str,
+) -> Result<(), TranslateError> {
+    let cell = match options.cell {
+        CellWidth::U8 => "u8",
+        CellWidth::U16 => "u16",
+        CellWidth::U32 => "u32",
+    };
+
+    // Programs which never read or move don't use every variable.
+    writeln!(output, "{indent}#[allow(unused_mut, unused_variables)]").unwrap();
+    writeln!(output, "{indent}pub fn {name}(input: &[u8]) -> Vec<u8> {{").unwrap();
+    writeln!(
+        output,
+        "{indent}    const TAPE_SIZE: usize = {};",
+        options.tape_size
+    )
+    .unwrap();
+    writeln!(output).unwrap();
+    writeln!(
+        output,
+        "{indent}    let mut tape = vec![0{cell}; TAPE_SIZE];"
+    )
+    .unwrap();
+    writeln!(output, "{indent}    let mut p = 0;").unwrap();
+    writeln!(output, "{indent}    let mut input = input.iter().copied();").unwrap();
+    writeln!(output, "{indent}    let mut output = Vec::new();").unwrap();
+    writeln!(output).unwrap();
+
+    let bytecode = program.bytecode();
+    let mut depth = 1;
+
+    for (address, op) in bytecode.ops().iter().enumerate() {
+        if let Op::JumpIfNonzero(_) = op {
+            depth -= 1;
+        }
+
+        let indent = format!("{indent}{}", "    ".repeat(depth));
+
+        match op {
+            Op::Add(value) => writeln!(
+                output,
+                "{indent}tape[p] = tape[p].wrapping_add({});",
+                options.cell.wrap(*value)
+            )
+            .unwrap(),
+
+            Op::Move(offset) => {
+                if options.forward(*offset) != 0 {
+                    writeln!(output, "{indent}p = {};", index(&options, *offset)).unwrap();
+                }
+            }
+
+            Op::Read => {
+                let eof = match options.eof {
+                    Eof::Zero => "0",
+                    Eof::MinusOne => "!0",
+                    Eof::Unchanged => "tape[p]",
+                };
+
+                writeln!(
+                    output,
+                    "{indent}tape[p] = input.next().map_or({eof}, {cell}::from);"
+                )
+                .unwrap();
+            }
+
+            Op::Write => writeln!(output, "{indent}output.push(tape[p] as u8);").unwrap(),
+
+            Op::Clear => writeln!(output, "{indent}tape[p] = 0;").unwrap(),
+
+            Op::MulAdd(targets) => {
+                for (offset, factor) in targets {
+                    let target = index(&options, *offset);
+
+                    writeln!(
+                        output,
+                        "{indent}tape[{target}] = tape[{target}].wrapping_add(tape[p].wrapping_mul({}));",
+                        options.cell.wrap(*factor),
+                    )
+                    .unwrap();
+                }
+
+                writeln!(output, "{indent}tape[p] = 0;").unwrap();
+            }
+
+            Op::Scan(step) => {
+                writeln!(output, "{indent}while tape[p] != 0 {{").unwrap();
+                writeln!(output, "{indent}    p = {};", index(&options, *step)).unwrap();
+                writeln!(output, "{indent}}}").unwrap();
+            }
+
+            Op::JumpIfZero(_) => {
+                writeln!(output, "{indent}while tape[p] != 0 {{").unwrap();
+                depth += 1;
+            }
+
+            Op::JumpIfNonzero(_) => writeln!(output, "{indent}}}").unwrap(),
+
+            Op::Dump => {}
+
+            Op::Define(_) | Op::Call | Op::Return => {
+                return Err(TranslateError::UnsupportedInstruction(
+                    bytecode.location(address),
+                ))
+            }
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "{indent}    output").unwrap();
+    writeln!(output, "{indent}}}").unwrap();
+
+    Ok(())
+}
+
+/// Gets a Rust expression for the index of the cell at an offset from the pointer.
+fn index(options: &TargetOptions, offset: isize) -> String {
+    match options.forward(offset) {
+        0 => "p".to_string(),
+        offset => format!("(p + {offset}) % TAPE_SIZE"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::backend::tests::{build, run_executable, scratch_dir, CORPUS};
+    use std::process::Command;
+
+    #[test]
+    fn names_must_be_identifiers() {
+        let program = Program::new("+.").unwrap();
+
+        for name in ["", "1st", "hello world", "fn", "self", "_", "naïve", "a::b"] {
+            assert_eq!(
+                transpile(&program, TargetOptions::default(), name),
+                Err(TranslateError::InvalidName(name.to_string()))
+            );
+        }
+
+        for name in ["run", "_run", "hello_world2"] {
+            assert!(transpile_module(&program, TargetOptions::default(), name).is_ok());
+        }
+    }
+
+    #[test]
+    fn compiled_programs_match_the_interpreter() {
+        let dir = scratch_dir("rust");
+        let source = dir.join("main.rs");
+        let executable = dir.join("main");
+
+        // Every case is a module, and the executable runs the one named by its argument.
+        let mut code = String::new();
+
+        for case in CORPUS {
+            code += &transpile_module(&case.program(), case.target(), case.name).unwrap();
+        }
+
+        code += "fn main() {\n";
+        code += "    use std::io::{Read, Write};\n";
+        code += "    let mut input = Vec::new();\n";
+        code += "    std::io::stdin().read_to_end(&mut input).unwrap();\n";
+        code += "    let output = match std::env::args().nth(1).unwrap().as_str() {\n";
+
+        for case in CORPUS {
+            writeln!(code, "        \"{0}\" => {0}::run(&input),", case.name).unwrap();
+        }
+
+        code += "        _ => unreachable!(),\n";
+        code += "    };\n";
+        code += "    std::io::stdout().write_all(&output).unwrap();\n";
+        code += "}\n";
+
+        std::fs::write(&source, code).unwrap();
+
+        build(
+            Command::new("rustc")
+                .arg("--edition=2021")
+                .arg("-o")
+                .arg(&executable)
+                .arg(&source),
+        );
+
+        for case in CORPUS {
+            let output = run_executable(&executable, &[case.name], case.input);
+            assert_eq!(output, case.expected(), "{}", case.name);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+//! Translates programs into WebAssembly text modules.
+//!
+//! A module imports two functions from `env`: `read_byte`, which returns the next input byte or
+//! -1 once input is exhausted, and `write_byte`, which takes an output byte. It exports its tape as
+//! `memory` and the program as `run`.
+
+use super::{CellWidth, TargetOptions, TranslateError};
+use crate::{
+    compiler::{Instruction, Program},
+    runner::Eof,
+};
+use std::fmt::Write;
+
+/// The size of a page of WebAssembly memory.
+const PAGE: usize = 65536;
+
+/// Translates a program into a WebAssembly text module.
+///
+/// Loops become a `block` which skips them when the current cell is zero, wrapping a `loop` which
+/// repeats while it is nonzero. Folded runs and recognised loop idioms become single statements.
+/// Its output matches `Program::run` with the same tape size, cell width and EOF convention byte
+/// for byte, except that input embedded in the program isn't included and dumps are ignored.
+///
+/// Returns `TranslateError::UnsupportedInstruction` if the program defines or calls procedures.
+pub fn transpile(program: &Program, options: TargetOptions) -> Result<String, TranslateError> {
+    let cells = Cells { options };
+    let pages = (options.tape_size * cells.size()).div_ceil(PAGE).max(1);
+
+    let mut output = String::new();
+
+    output += "(module\n";
+    output += "  (import \"env\" \"read_byte\" (func $read_byte (result i32)))\n";
+    output += "  (import \"env\" \"write_byte\" (func $write_byte (param i32)))\n";
+    writeln!(output, "  (memory (export \"memory\") {pages})").unwrap();
+    output += "  (func (export \"run\")\n";
+    output += "    (local $p i32)\n";
+    output += "    (local $v i32)\n";
+
+    // Each entry holds the remaining instructions of a list. Every list but the outermost is the
+    // body of a loop, which is closed once it runs out.
+    let mut stack = vec![program.instructions.iter()];
+
+    while !stack.is_empty() {
+        let indent = "  ".repeat(stack.len() * 2);
+        let instructions = stack.last_mut().unwrap();
+
+        let Some(node) = instructions.next() else {
+            stack.pop();
+
+            if !stack.is_empty() {
+                writeln!(output, "{indent}(br_if 0 {})))", cells.load(0)).unwrap();
+            }
+
+            continue;
+        };
+
+        match &node.instruction {
+            Instruction::Add(value) => {
+                let value = format!("(i32.add {} (i32.const {value}))", cells.load(0));
+                writeln!(output, "{indent}{}", cells.store(0, &value)).unwrap();
+            }
+
+            Instruction::Move(offset) => {
+                if options.forward(*offset) != 0 {
+                    writeln!(output, "{indent}(local.set $p {})", cells.index(*offset)).unwrap();
+                }
+            }
+
+            Instruction::Read => {
+                writeln!(output, "{indent}(local.set $v (call $read_byte))").unwrap();
+                writeln!(
+                    output,
+                    "{indent}(if (i32.ge_s (local.get $v) (i32.const 0))"
+                )
+                .unwrap();
+                writeln!(
+                    output,
+                    "{indent}  (then {})",
+                    cells.store(0, "(local.get $v)")
+                )
+                .unwrap();
+
+                match options.eof {
+                    Eof::Zero => writeln!(
+                        output,
+                        "{indent}  (else {})",
+                        cells.store(0, "(i32.const 0)")
+                    )
+                    .unwrap(),
+                    Eof::MinusOne => writeln!(
+                        output,
+                        "{indent}  (else {})",
+                        cells.store(0, "(i32.const -1)")
+                    )
+                    .unwrap(),
+                    Eof::Unchanged => {}
+                }
+
+                writeln!(output, "{indent})").unwrap();
+            }
+
+            Instruction::Write => writeln!(
+                output,
+                "{indent}(call $write_byte (i32.and {} (i32.const 255)))",
+                cells.load(0)
+            )
+            .unwrap(),
+
+            Instruction::Clear => {
+                writeln!(output, "{indent}{}", cells.store(0, "(i32.const 0)")).unwrap()
+            }
+
+            Instruction::MulAdd(targets) => {
+                writeln!(output, "{indent}(local.set $v {})", cells.load(0)).unwrap();
+
+                for (offset, factor) in targets {
+                    let value = format!(
+                        "(i32.add {} (i32.mul (local.get $v) (i32.const {factor})))",
+                        cells.load(*offset)
+                    );
+
+                    writeln!(output, "{indent}{}", cells.store(*offset, &value)).unwrap();
+                }
+
+                writeln!(output, "{indent}{}", cells.store(0, "(i32.const 0)")).unwrap();
+            }
+
+            Instruction::Scan(step) => {
+                writeln!(output, "{indent}(block (loop").unwrap();
+                writeln!(output, "{indent}  (br_if 1 (i32.eqz {}))", cells.load(0)).unwrap();
+                writeln!(output, "{indent}  (local.set $p {})", cells.index(*step)).unwrap();
+                writeln!(output, "{indent}  (br 0)))").unwrap();
+            }
+
+            Instruction::Loop(body) => {
+                writeln!(output, "{indent}(block").unwrap();
+                writeln!(output, "{indent}  (br_if 0 (i32.eqz {}))", cells.load(0)).unwrap();
+                writeln!(output, "{indent}  (loop").unwrap();
+                stack.push(body.iter());
+            }
+
+            Instruction::Dump => {}
+
+            Instruction::Procedure(_) | Instruction::Call => {
+                return Err(TranslateError::UnsupportedInstruction(node.span.start))
+            }
+        }
+    }
+
+    output += "  )\n";
+    output += ")\n";
+
+    Ok(output)
+}
+
+/// Builds expressions which access cells on the tape.
+struct Cells {
+    options: TargetOptions,
+}
+
+impl Cells {
+    /// Gets the size of each cell in bytes.
+    fn size(&self) -> usize {
+        self.options.cell.bits() as usize / 8
+    }
+
+    /// Gets an expression for the index of the cell at an offset from the pointer.
+    fn index(&self, offset: isize) -> String {
+        match self.options.forward(offset) {
+            0 => "(local.get $p)".to_string(),
+            offset => format!(
+                "(i32.rem_u (i32.add (local.get $p) (i32.const {offset})) (i32.const {}))",
+                self.options.tape_size
+            ),
+        }
+    }
+
+    /// Gets an expression for the address of the cell at an offset from the pointer.
+    fn address(&self, offset: isize) -> String {
+        match self.size() {
+            1 => self.index(offset),
+            size => format!("(i32.mul {} (i32.const {size}))", self.index(offset)),
+        }
+    }
+
+    /// Gets an expression for the value of the cell at an offset from the pointer.
+    fn load(&self, offset: isize) -> String {
+        let instruction = match self.options.cell {
+            CellWidth::U8 => "i32.load8_u",
+            CellWidth::U16 => "i32.load16_u",
+            CellWidth::U32 => "i32.load",
+        };
+
+        format!("({instruction} {})", self.address(offset))
+    }
+
+    /// Gets a statement which stores a value into the cell at an offset from the pointer,
+    /// truncating it to the width of a cell.
+    fn store(&self, offset: isize, value: &str) -> String {
+        let instruction = match self.options.cell {
+            CellWidth::U8 => "i32.store8",
+            CellWidth::U16 => "i32.store16",
+            CellWidth::U32 => "i32.store",
+        };
+
+        format!("({instruction} {} {value})", self.address(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::backend::tests::CORPUS;
+    use std::collections::VecDeque;
+    use wasmi::{Caller, Engine, Linker, Module, Store};
+
+    /// The input and output of a running module.
+    struct Io {
+        input: VecDeque<u8>,
+        output: Vec<u8>,
+    }
+
+    #[test]
+    fn modules_match_the_interpreter() {
+        let engine = Engine::default();
+
+        let mut linker = Linker::<Io>::new(&engine);
+        linker
+            .func_wrap("env", "read_byte", |mut caller: Caller<'_, Io>| {
+                caller.data_mut().input.pop_front().map_or(-1, i32::from)
+            })
+            .unwrap();
+        linker
+            .func_wrap(
+                "env",
+                "write_byte",
+                |mut caller: Caller<'_, Io>, byte: i32| caller.data_mut().output.push(byte as u8),
+            )
+            .unwrap();
+
+        for case in CORPUS {
+            let text = transpile(&case.program(), case.target()).unwrap();
+            let binary = ::wat::parse_str(&text).unwrap();
+            let module = Module::new(&engine, &binary[..]).unwrap();
+
+            let io = Io {
+                input: case.input.iter().copied().collect(),
+                output: Vec::new(),
+            };
+
+            let mut store = Store::new(&engine, io);
+            let instance = linker
+                .instantiate(&mut store, &module)
+                .unwrap()
+                .start(&mut store)
+                .unwrap();
+
+            let run = instance.get_typed_func::<(), ()>(&store, "run").unwrap();
+            run.call(&mut store, ()).unwrap();
+
+            assert_eq!(store.data().output, case.expected(), "{}", case.name);
+        }
+    }
+}
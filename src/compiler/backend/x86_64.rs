@@ -0,0 +1,256 @@
+//! An assembler which turns bytecode into x86-64 machine code, shared by the JIT and the ELF
+//! backend. Each of them provides an `Environment` which decides how code starts, stops and
+//! performs input and output.
+//!
+//! While compiled code runs, registers hold the following:
+//!
+//! - `rbx`: the address of the first cell.
+//! - `r12`: the index of the current cell.
+//! - `r13`: the number of cells on the tape.
+//!
+//! Every other register is free for environments to use, except that cell operations clobber
+//! `rax`, `rcx` and `rdx`.
+
+use super::TranslateError;
+use crate::compiler::bytecode::{Bytecode, Op};
+
+/// Decides how compiled code starts, stops and performs input and output.
+pub(crate) trait Environment {
+    /// Emits code which runs before the program, which must set up `rbx`, `r12` and `r13`.
+    fn prologue(&self, assembler: &mut Assembler);
+
+    /// Emits code which reads into the current cell.
+    fn read(&self, assembler: &mut Assembler);
+
+    /// Emits code which writes the current cell.
+    fn write(&self, assembler: &mut Assembler);
+
+    /// Emits code which runs after the program, which is also where exits lead.
+    fn epilogue(&self, assembler: &mut Assembler);
+}
+
+/// Assembles bytecode into machine code.
+pub(crate) struct Assembler {
+    code: Vec<u8>,
+
+    /// The size of each cell in bytes.
+    size: usize,
+
+    /// The offsets of jumps which leave the program early, which are patched to the epilogue.
+    exits: Vec<usize>,
+}
+
+impl Assembler {
+    /// Compiles bytecode for a tape of `len` cells which are each `size` bytes wide. Dumps are
+    /// ignored, and procedures are rejected with `TranslateError::UnsupportedInstruction`.
+    pub(crate) fn compile(
+        bytecode: &Bytecode,
+        len: usize,
+        size: usize,
+        environment: &impl Environment,
+    ) -> Result<Vec<u8>, TranslateError> {
+        let mut assembler = Self {
+            code: Vec::new(),
+            size,
+            exits: Vec::new(),
+        };
+
+        let forward = |offset: isize| offset.rem_euclid(len as isize) as usize;
+
+        environment.prologue(&mut assembler);
+
+        // The offsets of the `jz` targets of each loop which is currently open.
+        let mut loops = Vec::new();
+
+        for (address, op) in bytecode.ops().iter().enumerate() {
+            match op {
+                Op::Add(value) => assembler.add(*value),
+                Op::Move(offset) => assembler.move_by(forward(*offset)),
+                Op::Read => environment.read(&mut assembler),
+                Op::Write => environment.write(&mut assembler),
+                Op::Clear => assembler.clear(),
+
+                Op::MulAdd(targets) => {
+                    assembler.load_current(2);
+
+                    for (offset, factor) in targets {
+                        assembler.index(forward(*offset));
+
+                        // imul ecx, edx, factor
+                        assembler.emit(&[0x69, 0xCA]);
+                        assembler.emit(&factor.to_le_bytes());
+
+                        // add [target], cl or cx or ecx
+                        match size {
+                            1 => assembler.emit(&[0x00, 0x0C, assembler.sib(0)]),
+                            2 => assembler.emit(&[0x66, 0x01, 0x0C, assembler.sib(0)]),
+                            _ => assembler.emit(&[0x01, 0x0C, assembler.sib(0)]),
+                        }
+                    }
+
+                    assembler.clear();
+                }
+
+                Op::Scan(step) => {
+                    let start = assembler.code.len();
+                    assembler.test_current();
+                    let exit = assembler.jump(&[0x0F, 0x84]);
+                    assembler.move_by(forward(*step));
+                    let back = assembler.jump(&[0xE9]);
+                    assembler.patch(back, start);
+                    assembler.patch(exit, assembler.code.len());
+                }
+
+                Op::JumpIfZero(_) => {
+                    assembler.test_current();
+                    loops.push(assembler.jump(&[0x0F, 0x84]));
+                }
+
+                Op::JumpIfNonzero(_) => {
+                    let start = loops.pop().expect("bytecode loops are balanced");
+                    assembler.test_current();
+                    let back = assembler.jump(&[0x0F, 0x85]);
+                    assembler.patch(back, start + 4);
+                    assembler.patch(start, assembler.code.len());
+                }
+
+                Op::Dump => {}
+
+                Op::Define(_) | Op::Call | Op::Return => {
+                    return Err(TranslateError::UnsupportedInstruction(
+                        bytecode.location(address),
+                    ))
+                }
+            }
+        }
+
+        for exit in std::mem::take(&mut assembler.exits) {
+            assembler.patch(exit, assembler.code.len());
+        }
+
+        environment.epilogue(&mut assembler);
+
+        Ok(assembler.code)
+    }
+
+    /// Gets the SIB byte addressing `[rbx + index * size]`, where `index` is the number of a
+    /// register below `r8`. The current cell is addressed with `REX.X` and `r12`, whose low bits
+    /// are 4.
+    pub(crate) fn sib(&self, index: u8) -> u8 {
+        let scale = self.size.trailing_zeros() as u8;
+        scale << 6 | index << 3 | 0x03
+    }
+
+    /// Gets the SIB byte addressing the current cell, which needs a `REX.X` prefix.
+    pub(crate) fn current(&self) -> u8 {
+        self.sib(4)
+    }
+
+    /// Appends raw machine code.
+    pub(crate) fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    /// Emits a jump with a placeholder target, returning the offset of the target to patch.
+    pub(crate) fn jump(&mut self, opcode: &[u8]) -> usize {
+        self.emit(opcode);
+        self.emit(&[0; 4]);
+        self.code.len() - 4
+    }
+
+    /// Emits a jump which leaves the program early, once the jump's condition holds.
+    pub(crate) fn exit(&mut self, opcode: &[u8]) {
+        let exit = self.jump(opcode);
+        self.exits.push(exit);
+    }
+
+    /// Points the jump whose target is at `at` to an offset in the code.
+    pub(crate) fn patch(&mut self, at: usize, to: usize) {
+        let relative = to as i64 - (at as i64 + 4);
+        self.code[at..at + 4].copy_from_slice(&(relative as i32).to_le_bytes());
+    }
+
+    /// Gets the offset at which the next instruction will be emitted.
+    pub(crate) fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Sets the flags according to whether the current cell is zero. Clobbers `rax`.
+    fn test_current(&mut self) {
+        // test eax, eax
+        self.load_current(0);
+        self.emit(&[0x85, 0xC0]);
+    }
+
+    /// Loads the current cell into the register with the given number below `r8`, zero-extending
+    /// it to 32 bits.
+    pub(crate) fn load_current(&mut self, register: u8) {
+        let modrm = 0x04 | register << 3;
+
+        // movzx, or mov for 32-bit cells
+        match self.size {
+            1 => self.emit(&[0x42, 0x0F, 0xB6, modrm, self.current()]),
+            2 => self.emit(&[0x42, 0x0F, 0xB7, modrm, self.current()]),
+            _ => self.emit(&[0x42, 0x8B, modrm, self.current()]),
+        }
+    }
+
+    /// Stores the low bits of the register with the given number below `r8` into the current cell.
+    pub(crate) fn store_current(&mut self, register: u8) {
+        let modrm = 0x04 | register << 3;
+
+        match self.size {
+            1 => self.emit(&[0x42, 0x88, modrm, self.current()]),
+            2 => self.emit(&[0x66, 0x42, 0x89, modrm, self.current()]),
+            _ => self.emit(&[0x42, 0x89, modrm, self.current()]),
+        }
+    }
+
+    /// Adds a value to the current cell, wrapping on overflow.
+    pub(crate) fn add(&mut self, value: i32) {
+        match self.size {
+            1 => {
+                self.emit(&[0x42, 0x80, 0x04, self.current()]);
+                self.emit(&(value as u8).to_le_bytes());
+            }
+            2 => {
+                self.emit(&[0x66, 0x42, 0x81, 0x04, self.current()]);
+                self.emit(&(value as u16).to_le_bytes());
+            }
+            _ => {
+                self.emit(&[0x42, 0x81, 0x04, self.current()]);
+                self.emit(&value.to_le_bytes());
+            }
+        }
+    }
+
+    /// Sets the current cell to zero.
+    pub(crate) fn clear(&mut self) {
+        match self.size {
+            1 => self.emit(&[0x42, 0xC6, 0x04, self.current(), 0]),
+            2 => self.emit(&[0x66, 0x42, 0xC7, 0x04, self.current(), 0, 0]),
+            _ => self.emit(&[0x42, 0xC7, 0x04, self.current(), 0, 0, 0, 0]),
+        }
+    }
+
+    /// Computes the index of the cell at a forward offset from the pointer into `rax`, wrapping it
+    /// around the end of the tape. Clobbers `rcx`.
+    fn index(&mut self, offset: usize) {
+        // mov rax, offset
+        self.emit(&[0x48, 0xB8]);
+        self.emit(&(offset as u64).to_le_bytes());
+
+        // add rax, r12; mov rcx, rax; sub rcx, r13; cmovae rax, rcx
+        self.emit(&[0x4C, 0x01, 0xE0, 0x48, 0x89, 0xC1, 0x4C, 0x29, 0xE9]);
+        self.emit(&[0x48, 0x0F, 0x43, 0xC1]);
+    }
+
+    /// Moves the pointer by a forward offset, wrapping it around the end of the tape.
+    fn move_by(&mut self, offset: usize) {
+        if offset != 0 {
+            // mov r12, rax
+            self.index(offset);
+            self.emit(&[0x49, 0x89, 0xC4]);
+        }
+    }
+}
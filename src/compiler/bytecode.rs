@@ -0,0 +1,769 @@
+//! A flat bytecode representation of a program, along with a non-recursive interpreter for it.
+//!
+//! Loops are lowered into pairs of conditional jumps whose targets are resolved ahead of time, so
+//! running a program never recurses, no matter how deeply its loops are nested.
+
+use super::{
+    error::{Location, Span},
+    execution::{Limit, Status},
+    Instruction, Program,
+};
+use crate::runner::{
+    cell::{CellOverflow, CellValue},
+    tape::OutOfBounds,
+    MulAddError, Runner, ScanError, UnbalancedLoop,
+};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A single bytecode operation.
+pub enum Op {
+    /// Adds a value to the current cell.
+    Add(i32),
+
+    /// Moves the pointer by an offset, wrapping around the ends of the tape.
+    Move(isize),
+
+    /// Reads a value from input into the current cell.
+    Read,
+
+    /// Writes the current cell into output.
+    Write,
+
+    /// Sets the current cell to zero.
+    Clear,
+
+    /// For each `(offset, factor)` pair, adds the current cell times `factor` to the cell at
+    /// `offset`, then sets the current cell to zero.
+    MulAdd(Vec<(isize, i32)>),
+
+    /// Moves the pointer by a step until it reaches a zero cell.
+    Scan(isize),
+
+    /// Jumps to the given address if the current cell is zero. The address is always the one right
+    /// after the matching `JumpIfNonzero`.
+    JumpIfZero(usize),
+
+    /// Jumps to the given address if the current cell is nonzero. The address is always the one
+    /// right after the matching `JumpIfZero`.
+    JumpIfNonzero(usize),
+
+    /// Records a snapshot of the tape.
+    Dump,
+
+    /// Defines a procedure starting at the next address, identified by the value of the current
+    /// cell, then jumps to the given address. The address is always the one right after the
+    /// matching `Return`.
+    Define(usize),
+
+    /// Runs the procedure identified by the value of the current cell.
+    Call,
+
+    /// Returns from the procedure which is running.
+    Return,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A program compiled into a flat list of operations with resolved jump targets.
+pub struct Bytecode {
+    ops: Vec<Op>,
+
+    /// The span of source each operation came from. Both jumps of a loop have the span of the
+    /// whole loop, and the start and end of a procedure have the span of the whole procedure.
+    spans: Vec<Span>,
+}
+
+impl Bytecode {
+    /// Compiles a program into bytecode.
+    pub fn new(program: &Program) -> Self {
+        let mut ops = Vec::new();
+        let mut spans = Vec::new();
+
+        // Each entry holds the remaining instructions of a list and, for the bodies of loops and
+        // procedures, the address of the `JumpIfZero` or `Define` which opened it.
+        let mut stack = vec![(program.instructions.iter(), None)];
+
+        while let Some((instructions, start)) = stack.last_mut() {
+            let Some(node) = instructions.next() else {
+                if let Some(start) = *start {
+                    spans.push(spans[start]);
+
+                    if matches!(ops[start], Op::Define(_)) {
+                        ops.push(Op::Return);
+                        ops[start] = Op::Define(ops.len());
+                    } else {
+                        ops.push(Op::JumpIfNonzero(start + 1));
+                        ops[start] = Op::JumpIfZero(ops.len());
+                    }
+                }
+
+                stack.pop();
+                continue;
+            };
+
+            spans.push(node.span);
+
+            match &node.instruction {
+                Instruction::Add(value) => ops.push(Op::Add(*value)),
+                Instruction::Move(offset) => ops.push(Op::Move(*offset)),
+                Instruction::Read => ops.push(Op::Read),
+                Instruction::Write => ops.push(Op::Write),
+                Instruction::Clear => ops.push(Op::Clear),
+                Instruction::MulAdd(targets) => ops.push(Op::MulAdd(targets.clone())),
+                Instruction::Scan(step) => ops.push(Op::Scan(*step)),
+                Instruction::Dump => ops.push(Op::Dump),
+                Instruction::Call => ops.push(Op::Call),
+
+                Instruction::Loop(body) => {
+                    // The target is patched once the end of the loop is reached.
+                    ops.push(Op::JumpIfZero(0));
+                    stack.push((body.iter(), Some(ops.len() - 1)));
+                }
+
+                Instruction::Procedure(body) => {
+                    ops.push(Op::Define(0));
+                    stack.push((body.iter(), Some(ops.len() - 1)));
+                }
+            }
+        }
+
+        Self { ops, spans }
+    }
+
+    /// Gets the operations in this bytecode.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Checks if this bytecode dumps the tape or uses procedures, which only the interpreter
+    /// supports.
+    pub fn extended(&self) -> bool {
+        self.ops
+            .iter()
+            .any(|op| matches!(op, Op::Dump | Op::Define(_) | Op::Call | Op::Return))
+    }
+
+    /// Gets the source location of the operation at an address. Both jumps of a loop are located
+    /// at its `[`, and the start and end of a procedure at its `(`.
+    pub fn location(&self, address: usize) -> Location {
+        self.spans[address].start
+    }
+
+    /// Gets the span of source the operation at an address came from.
+    pub fn span(&self, address: usize) -> Span {
+        self.spans[address]
+    }
+
+    /// Runs this bytecode on a runner until it completes, returning why it stopped. Without a
+    /// limit, this is `Status::Halted` unless the program failed.
+    pub fn run<const N: usize, T: CellValue, I: Read, O: Write>(
+        &self,
+        runner: &mut Runner<N, T, I, O>,
+    ) -> Status {
+        self.run_limited(runner, Limit::default()).0
+    }
+
+    /// Runs this bytecode on a runner until it completes or reaches a limit, returning why it
+    /// stopped and the number of steps it took, as counted by `Limit::fuel`.
+    pub fn run_limited<const N: usize, T: CellValue, I: Read, O: Write>(
+        &self,
+        runner: &mut Runner<N, T, I, O>,
+        limit: Limit,
+    ) -> (Status, u64) {
+        self.run_observed(runner, limit, |_| {})
+    }
+
+    /// Runs this bytecode like `run_limited`, calling `observe` with the address of each operation
+    /// right before it is executed.
+    pub(super) fn run_observed<const N: usize, T: CellValue, I: Read, O: Write>(
+        &self,
+        runner: &mut Runner<N, T, I, O>,
+        limit: Limit,
+        mut observe: impl FnMut(usize),
+    ) -> (Status, u64) {
+        let mut execution = Execution::new(runner);
+        let mut steps = 0;
+
+        let status = loop {
+            if execution.finished(self) {
+                break Status::Halted;
+            }
+
+            if let Some(status) = limit.exceeded(steps) {
+                break status;
+            }
+
+            steps += 1;
+            observe(execution.address);
+
+            if let Err(status) = execution.step_limited(self, runner, limit, &mut steps) {
+                break status;
+            }
+        };
+
+        match runner.flush() {
+            Err(error) if status == Status::Halted => (Status::Io(error.kind()), steps),
+            _ => (status, steps),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The state of a runner partway through running some bytecode, other than the runner itself.
+pub(super) struct Execution {
+    /// The address of the next operation to execute.
+    pub(super) address: usize,
+
+    /// Whether to check that loops leave the pointer where they found it.
+    check_balance: bool,
+
+    /// The pointer position at the start of each loop which is currently running. This is only
+    /// tracked when the runner asks for balance checks.
+    loop_positions: Vec<isize>,
+
+    /// The address each defined procedure starts at, by the value identifying it.
+    procedures: HashMap<u32, usize>,
+
+    /// The address to return to from each procedure which is currently running.
+    calls: Vec<usize>,
+
+    /// The pointer position the scan at the next address started at, if it was stopped partway by
+    /// a limit.
+    scan_start: Option<isize>,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// The state of an execution before an operation, which is enough to undo the operation.
+pub(super) struct Checkpoint {
+    address: usize,
+
+    /// The number of loops whose position was being tracked.
+    loops: usize,
+
+    /// The position at the start of the innermost loop's current iteration, if it was tracked.
+    innermost: Option<isize>,
+
+    /// The number of procedures which were running.
+    calls: usize,
+
+    /// The address to return to from the innermost running procedure, if there was one.
+    returning: Option<usize>,
+
+    /// If the operation defines a procedure, the value identifying it along with the address of
+    /// the procedure it replaces, if any.
+    defined: Option<(u32, Option<usize>)>,
+
+    /// The position the scan at the address started at, if it was stopped partway.
+    scan_start: Option<isize>,
+}
+
+impl Execution {
+    /// Starts executing bytecode from its first operation on a runner.
+    pub(super) fn new<const N: usize, T: CellValue, I, O>(runner: &Runner<N, T, I, O>) -> Self {
+        Self {
+            address: 0,
+            check_balance: runner.options().check_balance,
+            loop_positions: Vec::new(),
+            procedures: HashMap::new(),
+            calls: Vec::new(),
+            scan_start: None,
+        }
+    }
+
+    /// Saves the state of this execution before the next operation, so the operation can be
+    /// undone. This needs the value of the current cell in case the operation defines a procedure.
+    pub(super) fn checkpoint<T: CellValue>(&self, bytecode: &Bytecode, current: T) -> Checkpoint {
+        let defined = matches!(bytecode.ops.get(self.address), Some(Op::Define(_))).then(|| {
+            let id = current.to_u32();
+            (id, self.procedures.get(&id).copied())
+        });
+
+        Checkpoint {
+            address: self.address,
+            loops: self.loop_positions.len(),
+            innermost: self.loop_positions.last().copied(),
+            calls: self.calls.len(),
+            returning: self.calls.last().copied(),
+            defined,
+            scan_start: self.scan_start,
+        }
+    }
+
+    /// Restores the state of this execution from before an operation. Every operation executed
+    /// since the checkpoint was saved must be undone first.
+    pub(super) fn restore(&mut self, checkpoint: Checkpoint) {
+        self.address = checkpoint.address;
+        self.scan_start = checkpoint.scan_start;
+        self.loop_positions.truncate(checkpoint.loops);
+
+        // A single operation only ever changes or removes the innermost loop's position.
+        if let Some(start) = checkpoint.innermost {
+            if self.loop_positions.len() < checkpoint.loops {
+                self.loop_positions.push(start);
+            } else if let Some(position) = self.loop_positions.last_mut() {
+                *position = start;
+            }
+        }
+
+        // Likewise, a single operation only ever enters or leaves one procedure.
+        self.calls.truncate(checkpoint.calls);
+
+        if let Some(address) = checkpoint.returning {
+            if self.calls.len() < checkpoint.calls {
+                self.calls.push(address);
+            }
+        }
+
+        match checkpoint.defined {
+            Some((id, Some(start))) => {
+                self.procedures.insert(id, start);
+            }
+
+            Some((id, None)) => {
+                self.procedures.remove(&id);
+            }
+
+            None => {}
+        }
+    }
+
+    /// Checks if every operation has been executed.
+    pub(super) fn finished(&self, bytecode: &Bytecode) -> bool {
+        self.address >= bytecode.ops.len()
+    }
+
+    /// Checks if the next operation is a scan which was stopped partway by a limit.
+    pub(super) fn scanning(&self) -> bool {
+        self.scan_start.is_some()
+    }
+
+    /// Executes the next operation, returning the reason the program stopped if it failed. The
+    /// bytecode must not be finished.
+    ///
+    /// The steps a scan takes past its first are added to `steps`, and if that reaches the limit,
+    /// the scan stops where it is. It is still the next operation then, and executing it again
+    /// carries on from that cell.
+    pub(super) fn step_limited<const N: usize, T: CellValue, I: Read, O: Write>(
+        &mut self,
+        bytecode: &Bytecode,
+        runner: &mut Runner<N, T, I, O>,
+        limit: Limit,
+        steps: &mut u64,
+    ) -> Result<(), Status> {
+        let op = &bytecode.ops[self.address];
+        let location = bytecode.location(self.address);
+        let out_of_bounds = |OutOfBounds { index }| Status::OutOfBounds { location, index };
+        let overflow = |CellOverflow { index }| Status::Overflow { location, index };
+
+        self.address += 1;
+
+        match op {
+            Op::Add(value) => runner.add(*value).map_err(overflow),
+
+            Op::Move(offset) => runner.move_by(*offset).map_err(out_of_bounds),
+
+            Op::Read => runner.read().map_err(|error| Status::Io(error.kind())),
+
+            Op::Write => runner.write().map_err(|error| Status::Io(error.kind())),
+
+            Op::Clear => {
+                runner.clear();
+                Ok(())
+            }
+
+            Op::MulAdd(targets) => runner.mul_add(targets).map_err(|error| match error {
+                MulAddError::OutOfBounds(error) => out_of_bounds(error),
+                MulAddError::Overflow(error) => overflow(error),
+            }),
+
+            Op::Scan(step) => {
+                let start = self.scan_start.take().unwrap_or(runner.position());
+                let result = match runner.scan(*step, limit, steps) {
+                    Ok(()) => Ok(()),
+                    Err(ScanError::OutOfBounds(error)) => Err(out_of_bounds(error)),
+                    Err(ScanError::Limit(status)) => {
+                        self.address -= 1;
+                        self.scan_start = Some(start);
+                        return Err(status);
+                    }
+                };
+
+                if self.check_balance && runner.position() != start {
+                    runner.report_unbalanced(UnbalancedLoop {
+                        location: Some(location),
+                        start,
+                        end: runner.position(),
+                    });
+                }
+
+                result
+            }
+
+            Op::JumpIfZero(target) => {
+                if runner.get() == T::ZERO {
+                    self.address = *target;
+                } else if self.check_balance {
+                    self.loop_positions.push(runner.position());
+                }
+
+                Ok(())
+            }
+
+            Op::JumpIfNonzero(target) => {
+                if self.check_balance {
+                    let start = self
+                        .loop_positions
+                        .last_mut()
+                        .expect("a loop is always running when its end is reached");
+
+                    if runner.position() != *start {
+                        runner.report_unbalanced(UnbalancedLoop {
+                            location: Some(location),
+                            start: *start,
+                            end: runner.position(),
+                        });
+                    }
+
+                    // Each iteration is checked against the position it started at.
+                    *start = runner.position();
+                }
+
+                if runner.get() != T::ZERO {
+                    self.address = *target;
+                } else if self.check_balance {
+                    self.loop_positions.pop();
+                }
+
+                Ok(())
+            }
+
+            Op::Dump => {
+                runner.dump(Some(location));
+                Ok(())
+            }
+
+            Op::Define(end) => {
+                self.procedures.insert(runner.get().to_u32(), self.address);
+                self.address = *end;
+                Ok(())
+            }
+
+            Op::Call => {
+                let id = runner.get().to_u32();
+
+                let Some(&start) = self.procedures.get(&id) else {
+                    return Err(Status::UndefinedProcedure { location, id });
+                };
+
+                self.calls.push(self.address);
+                self.address = start;
+                Ok(())
+            }
+
+            Op::Return => {
+                self.address = self
+                    .calls
+                    .pop()
+                    .expect("a procedure is always running when its end is reached");
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            error::Location,
+            execution::{Limit, Status},
+            Program,
+        },
+        runner::{cell::Overflow, tape::TapePolicy, Runner, RunnerOptions, UnbalancedLoop},
+    };
+    use std::time::{Duration, Instant};
+
+    /// Fills a wrapping tape with nonzero cells, so the scan eventually never finds a zero cell.
+    const ENDLESS_SCAN: &str = "+[[>]+]";
+
+    #[test]
+    fn scans_take_a_step_for_each_cell() {
+        // Eight operations set up the tape, then the scan takes one step and moves past four cells.
+        let program = Program::new("+>+>+>+<<<[>]").unwrap();
+        let outcome = program.run_with_fuel::<64, u8>(b"", 1_000);
+
+        assert_eq!(outcome.status, Status::Halted);
+        assert_eq!(outcome.steps, 13);
+    }
+
+    #[test]
+    fn scans_run_out_of_fuel() {
+        let program = Program::new(ENDLESS_SCAN).unwrap();
+        let outcome =
+            program.run_limited::<64, u8>(b"", RunnerOptions::default(), Limit::fuel(100_000));
+
+        assert_eq!(outcome.status, Status::OutOfFuel);
+        assert_eq!(outcome.steps, 100_000);
+    }
+
+    #[test]
+    fn scans_stop_at_the_deadline() {
+        let program = Program::new(ENDLESS_SCAN).unwrap();
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let outcome =
+            program.run_limited::<64, u8>(b"", RunnerOptions::default(), Limit::deadline(deadline));
+
+        assert_eq!(outcome.status, Status::DeadlineExceeded);
+    }
+
+    #[test]
+    fn scans_stop_at_the_timeout() {
+        let program = Program::new(ENDLESS_SCAN).unwrap();
+        let outcome = program.run_with_timeout::<64, u8>(b"", Duration::from_millis(50));
+
+        assert_eq!(outcome.status, Status::DeadlineExceeded);
+    }
+
+    #[test]
+    fn moving_off_an_error_tape_reports_where() {
+        let options = RunnerOptions {
+            tape: TapePolicy::Error,
+            ..RunnerOptions::default()
+        };
+
+        let bytecode = Program::new(">>+\n<<<").unwrap().bytecode();
+        let mut runner = Runner::<4>::with_options(b"", options);
+        let location = Location {
+            offset: 4,
+            line: 2,
+            column: 1,
+        };
+
+        // The pointer stays where it was before the move which failed.
+        assert_eq!(
+            bytecode.run(&mut runner),
+            Status::OutOfBounds {
+                location,
+                index: -1
+            }
+        );
+
+        assert_eq!(runner.position(), 2);
+
+        let bytecode = Program::new("+>>>>").unwrap().bytecode();
+        let mut runner = Runner::<4>::with_options(b"", options);
+
+        assert!(matches!(
+            bytecode.run(&mut runner),
+            Status::OutOfBounds { index: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn growing_tapes_extend_on_demand() {
+        let grow = RunnerOptions {
+            tape: TapePolicy::Grow,
+            ..RunnerOptions::default()
+        };
+
+        let bytecode = Program::new(">>>>>>+").unwrap().bytecode();
+        let mut runner = Runner::<4>::with_options(b"", grow);
+
+        assert_eq!(bytecode.run(&mut runner), Status::Halted);
+        assert_eq!(runner.position(), 6);
+        assert_eq!(runner.cells(), [0, 0, 0, 0, 0, 0, 1, 0]);
+
+        // Growing to the right doesn't allow moving left of the first cell.
+        let bytecode = Program::new("<").unwrap().bytecode();
+        let mut runner = Runner::<4>::with_options(b"", grow);
+
+        assert!(matches!(
+            bytecode.run(&mut runner),
+            Status::OutOfBounds { index: -1, .. }
+        ));
+
+        let grow_both = RunnerOptions {
+            tape: TapePolicy::GrowBoth,
+            ..RunnerOptions::default()
+        };
+
+        let bytecode = Program::new("+<<+>>>>>>+").unwrap().bytecode();
+        let mut runner = Runner::<4>::with_options(b"", grow_both);
+
+        // Growing to the left shifts the first cell to index 4, but not its position.
+        assert_eq!(bytecode.run(&mut runner), Status::Halted);
+        assert_eq!(runner.position(), 4);
+        assert_eq!(runner.index(), 8);
+        assert_eq!(
+            runner.cells(),
+            [0, 0, 1, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn unbalanced_loops_run_unchecked_by_default() {
+        let program = Program::new("+>+>+<<[[-]>]>[>]").unwrap();
+        let outcome = program.run_with_options::<64, u8>(b"", RunnerOptions::default());
+
+        assert!(outcome.halted());
+        assert_eq!(outcome.runner.position(), 4);
+        assert!(outcome.runner.unbalanced_loops().is_empty());
+    }
+
+    #[test]
+    fn unbalanced_loops_are_reported_once_when_checked() {
+        let program = Program::new("+>+>+<<[[-]>]++[>]").unwrap();
+        let options = RunnerOptions {
+            check_balance: true,
+            ..RunnerOptions::default()
+        };
+
+        let outcome = program.run_with_options::<64, u8>(b"", options);
+
+        // The loop is unbalanced on each of its three iterations, but it's only reported the first
+        // time, while the scan is reported separately.
+        assert!(outcome.halted());
+        assert_eq!(
+            outcome.runner.unbalanced_loops(),
+            [
+                UnbalancedLoop {
+                    location: Some(Location {
+                        offset: 7,
+                        line: 1,
+                        column: 8,
+                    }),
+                    start: 0,
+                    end: 1,
+                },
+                UnbalancedLoop {
+                    location: Some(Location {
+                        offset: 15,
+                        line: 1,
+                        column: 16,
+                    }),
+                    start: 3,
+                    end: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn balanced_loops_are_not_reported() {
+        let program = Program::new("++++[>++[>+<-]<-]>>.").unwrap();
+        let options = RunnerOptions {
+            check_balance: true,
+            ..RunnerOptions::default()
+        };
+
+        let outcome = program.run_with_options::<64, u8>(b"", options);
+
+        assert_eq!(outcome.runner.output(), &[8]);
+        assert!(outcome.runner.unbalanced_loops().is_empty());
+    }
+
+    #[test]
+    fn wide_cells_truncate_output() {
+        // 16 * 16 + 1 = 257, which is written as 1.
+        let program = Program::new("++++++++++++++++[>++++++++++++++++<-]>+.").unwrap();
+        let runner = program.run::<4, u16>(b"");
+        assert_eq!(runner.cells()[1], 257);
+        assert_eq!(runner.output(), &[1]);
+
+        // 8 to the sixth power plus 1 = 262145, which is written as 1.
+        let source = format!(
+            "++++++++{}{}>>>>>+.",
+            "[>++++++++".repeat(5),
+            "<-]".repeat(5)
+        );
+        let runner = Program::new(&source).unwrap().run::<8, u32>(b"");
+        assert_eq!(runner.cells()[5], 262_145);
+        assert_eq!(runner.output(), &[1]);
+    }
+
+    #[test]
+    fn wide_cells_widen_input() {
+        // A read byte is never negative, so 0xFF plus one carries into the next byte.
+        let program = Program::new(",+.,-.").unwrap();
+
+        let runner = program.run::<4, u16>(b"\xFF\0");
+        assert_eq!(runner.cells()[0], u16::MAX);
+        assert_eq!(runner.output(), &[0, 0xFF]);
+
+        let runner = program.run::<4, u32>(b"\xFF\0");
+        assert_eq!(runner.cells()[0], u32::MAX);
+        assert_eq!(runner.output(), &[0, 0xFF]);
+
+        let runner = program.run::<4, u8>(b"\xFF\0");
+        assert_eq!(runner.output(), &[0, 0xFF]);
+    }
+
+    #[test]
+    fn overflowing_a_checked_cell_reports_where() {
+        let options = RunnerOptions {
+            overflow: Overflow::Error,
+            ..RunnerOptions::default()
+        };
+
+        let program = Program::new(">+<\n-").unwrap();
+        let outcome = program.run_with_options::<4, u8>(b"", options);
+        let location = Location {
+            offset: 4,
+            line: 2,
+            column: 1,
+        };
+
+        assert_eq!(outcome.status, Status::Overflow { location, index: 0 });
+        assert_eq!(outcome.runner.cells(), [0, 1, 0, 0]);
+
+        // 256 only overflows byte cells, and a run which ends in range never overflows.
+        let program = Program::new(&format!("-+{}.", "+".repeat(256))).unwrap();
+        let outcome = program.run_with_options::<4, u8>(b"", options);
+        assert!(matches!(outcome.status, Status::Overflow { index: 0, .. }));
+
+        let outcome = program.run_with_options::<4, u16>(b"", options);
+        assert!(outcome.halted());
+        assert_eq!(outcome.runner.cells()[0], 256);
+    }
+
+    #[test]
+    fn overflowing_a_multiply_add_target_changes_nothing() {
+        let options = RunnerOptions {
+            overflow: Overflow::Error,
+            ..RunnerOptions::default()
+        };
+
+        let program = Program::new("++++++++++++++++[>++++++++++++++++<-]").unwrap();
+        let outcome = program.run_with_options::<4, u8>(b"", options);
+        let location = Location {
+            offset: 16,
+            line: 1,
+            column: 17,
+        };
+
+        assert_eq!(outcome.status, Status::Overflow { location, index: 1 });
+        assert_eq!(outcome.runner.cells(), [16, 0, 0, 0]);
+
+        let outcome = program.run_with_options::<4, u16>(b"", options);
+        assert!(outcome.halted());
+        assert_eq!(outcome.runner.cells(), [0, 256, 0, 0]);
+    }
+
+    #[test]
+    fn clears_never_overflow() {
+        let options = RunnerOptions {
+            overflow: Overflow::Error,
+            ..RunnerOptions::default()
+        };
+
+        let program = Program::new("+++[-]+++[+]+").unwrap();
+        let outcome = program.run_with_options::<4, u8>(b"", options);
+
+        assert!(outcome.halted());
+        assert_eq!(outcome.runner.cells(), [1, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,474 @@
+//! A debugger which runs a program one operation at a time, stopping at breakpoints.
+
+use super::{
+    bytecode::{Bytecode, Execution, Op},
+    error::{Location, ParseError},
+    execution::{Limit, Status},
+    recorder::Recorder,
+    Program,
+};
+use crate::runner::{cell::CellValue, Runner, RunnerOptions};
+use std::collections::{BTreeSet, VecDeque};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// The reason a debugger stopped running.
+pub enum Stop {
+    /// The next operation has a breakpoint on it.
+    Breakpoint {
+        /// The address of the next operation.
+        address: usize,
+
+        /// The source location of the next operation.
+        location: Location,
+    },
+
+    /// The program stopped running.
+    Finished(Status),
+}
+
+#[derive(Clone, Debug)]
+/// A debugger which runs a program one bytecode operation at a time.
+///
+/// Breakpoints are set by source offset and stop the program before the first operation at or
+/// after that offset. Loops which were replaced by a single operation, such as `[->+<]`, run in one
+/// step, so breakpoints inside them stop the program after the loop instead. Scans such as `[>]`
+/// are the exception, taking a step for each cell they move past, as they do under `Limit::fuel`.
+///
+/// Between steps, the tape, the pointer and the pending input can all be inspected and modified.
+///
+/// While recording, every step also saves how to undo it, so the program can be stepped backwards,
+/// rewound to the last write of a cell, or rewound to before it wrote a byte of output. Changes made
+/// between steps aren't recorded, so stepping back past them keeps them.
+pub struct Debugger<const N: usize, T: CellValue = u8> {
+    bytecode: Bytecode,
+    runner: Runner<N, T>,
+    execution: Execution,
+
+    /// The addresses of operations with breakpoints on them.
+    breakpoints: BTreeSet<usize>,
+
+    steps: u64,
+
+    /// The address `run` last stopped at for a breakpoint, as long as nothing has happened since,
+    /// so running again continues past that breakpoint rather than stopping at it straight away.
+    resumed: Option<usize>,
+
+    /// The reason the program stopped, once it has.
+    status: Option<Status>,
+
+    /// How to undo each step taken since recording started, if it has.
+    recorder: Option<Recorder<T>>,
+}
+
+impl<const N: usize, T: CellValue> Debugger<N, T> {
+    /// Parses source into a debugger for it, with a breakpoint at every `#` character. Returns an
+    /// error if the source is malformed.
+    pub fn new(source: &str, input: &[u8], options: RunnerOptions) -> Result<Self, ParseError> {
+        let mut debugger = Self::with_program(&Program::new(source)?, input, options);
+
+        for (offset, _) in source.match_indices('#') {
+            debugger.add_breakpoint(offset);
+        }
+
+        Ok(debugger)
+    }
+
+    /// Constructs a debugger for a program with no breakpoints.
+    pub fn with_program(program: &Program, input: &[u8], options: RunnerOptions) -> Self {
+        let mut runner = Runner::with_options(input, options);
+        runner.queue_input(&program.input);
+
+        Self {
+            bytecode: program.bytecode(),
+            execution: Execution::new(&runner),
+            runner,
+            breakpoints: BTreeSet::new(),
+            steps: 0,
+            resumed: None,
+            status: None,
+            recorder: None,
+        }
+    }
+
+    /// Gets the bytecode being debugged.
+    pub fn bytecode(&self) -> &Bytecode {
+        &self.bytecode
+    }
+
+    /// Gets the runner the program is running on.
+    pub fn runner(&self) -> &Runner<N, T> {
+        &self.runner
+    }
+
+    /// Gets the address of the next operation to execute.
+    pub fn address(&self) -> usize {
+        self.execution.address
+    }
+
+    /// Gets the next operation to execute, or `None` if the program has finished.
+    pub fn next_op(&self) -> Option<&Op> {
+        self.bytecode.ops().get(self.execution.address)
+    }
+
+    /// Gets the source location of the next operation to execute, or `None` if the program has
+    /// finished.
+    pub fn location(&self) -> Option<Location> {
+        self.next_op()
+            .map(|_| self.bytecode.location(self.execution.address))
+    }
+
+    /// Gets the number of steps taken so far, as counted by `Limit::fuel`.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Gets the reason the program stopped, or `None` if it can still run.
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+
+    /// Adds a breakpoint before the first operation at or after a source offset, returning its
+    /// address, or `None` if there are no operations after the offset.
+    pub fn add_breakpoint(&mut self, offset: usize) -> Option<usize> {
+        let address = self.address_at(offset)?;
+        self.breakpoints.insert(address);
+        Some(address)
+    }
+
+    /// Removes the breakpoint for a source offset, returning whether there was one.
+    pub fn remove_breakpoint(&mut self, offset: usize) -> bool {
+        self.address_at(offset)
+            .is_some_and(|address| self.breakpoints.remove(&address))
+    }
+
+    /// Removes every breakpoint.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Gets the addresses of the operations with breakpoints on them, in order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Gets the address of the first operation at or after a source offset. The ends of loops and
+    /// procedures are placed at their closing bracket rather than their location, so an offset
+    /// after the last operation in a body maps to the end of that body.
+    fn address_at(&self, offset: usize) -> Option<usize> {
+        self.bytecode
+            .ops()
+            .iter()
+            .enumerate()
+            .position(|(address, op)| {
+                let position = match op {
+                    Op::JumpIfNonzero(_) | Op::Return => self.bytecode.span(address).end.offset - 1,
+                    _ => self.bytecode.location(address).offset,
+                };
+
+                position >= offset
+            })
+    }
+
+    /// Takes a single step, returning the reason the program stopped if it did. Steps are counted
+    /// like `Limit::fuel`, so this executes one operation, except that a scan takes a step to
+    /// start and then another for each cell it moves past. That way, a scan which never finds a
+    /// zero cell can still be stepped through.
+    pub fn step(&mut self) -> Option<Status> {
+        self.resumed = None;
+
+        if self.status.is_none() {
+            if self.execution.finished(&self.bytecode) {
+                self.status = Some(Status::Halted);
+            } else {
+                self.steps += 1;
+
+                // Starting an operation uses up the step unless it continues a scan, in which case
+                // the step is spent moving to the next cell.
+                let limit = Limit::fuel(1);
+                let mut used = u64::from(!self.execution.scanning());
+
+                let result = match &mut self.recorder {
+                    Some(recorder) => recorder.step(
+                        &mut self.execution,
+                        &self.bytecode,
+                        &mut self.runner,
+                        limit,
+                        &mut used,
+                    ),
+
+                    None => self.execution.step_limited(
+                        &self.bytecode,
+                        &mut self.runner,
+                        limit,
+                        &mut used,
+                    ),
+                };
+
+                if self.execution.scanning() {
+                    // The scan stopped because the step is over, not because of the program.
+                } else if let Err(status) = result {
+                    self.status = Some(status);
+                } else if self.execution.finished(&self.bytecode) {
+                    self.status = Some(Status::Halted);
+                }
+            }
+        }
+
+        self.status
+    }
+
+    /// Runs until the next operation has a breakpoint on it or the program stops. This stops
+    /// before executing anything if the next operation already has a breakpoint on it, unless the
+    /// last call stopped there, so calling this again continues past it.
+    pub fn run(&mut self) -> Stop {
+        let mut resumed = self.resumed.take();
+
+        loop {
+            let address = self.execution.address;
+
+            // Breakpoints stop the program before scans, not between the cells they move past.
+            if self.status.is_none()
+                && !self.execution.scanning()
+                && resumed.take() != Some(address)
+                && self.breakpoints.contains(&address)
+            {
+                self.resumed = Some(address);
+
+                return Stop::Breakpoint {
+                    address,
+                    location: self.bytecode.location(address),
+                };
+            }
+
+            if let Some(status) = self.step() {
+                return Stop::Finished(status);
+            }
+        }
+    }
+
+    /// Starts or stops recording how to undo each step. Only steps taken while recording can be
+    /// stepped back over, and stopping discards everything recorded so far.
+    pub fn record(&mut self, enabled: bool) {
+        if enabled != self.recorder.is_some() {
+            self.recorder = enabled.then(Recorder::new);
+        }
+    }
+
+    /// Gets the number of recorded steps which can be stepped back over.
+    pub fn recorded(&self) -> usize {
+        self.recorder.as_ref().map_or(0, Recorder::len)
+    }
+
+    /// Undoes the last recorded step, returning whether there was one.
+    pub fn step_back(&mut self) -> bool {
+        let Some(recorder) = &mut self.recorder else {
+            return false;
+        };
+
+        if !recorder.undo(&mut self.execution, &mut self.runner) {
+            return false;
+        }
+
+        self.steps -= 1;
+        self.resumed = None;
+        self.status = None;
+
+        true
+    }
+
+    /// Undoes up to `count` recorded steps, returning how many were undone.
+    pub fn rewind(&mut self, count: usize) -> usize {
+        (0..count).take_while(|_| self.step_back()).count()
+    }
+
+    /// Rewinds to right before the last recorded step which wrote to the cell at an index, so it is
+    /// the next step to execute. Returns whether there was such a step, without rewinding at all if
+    /// there wasn't.
+    pub fn rewind_to_write(&mut self, index: usize) -> bool {
+        let position = index as isize - self.runner.extent().1 as isize;
+
+        match self.recorder.as_ref().and_then(|r| r.since_write(position)) {
+            Some(count) => self.rewind(count) == count,
+            None => false,
+        }
+    }
+
+    /// Rewinds to right before the step which wrote the output byte at an index, so only the bytes
+    /// before it have been written. Returns whether that step was recorded, without rewinding at
+    /// all if it wasn't.
+    pub fn rewind_to_output(&mut self, index: usize) -> bool {
+        let written = self.runner.output().len();
+
+        if index >= written {
+            return false;
+        }
+
+        match self
+            .recorder
+            .as_ref()
+            .and_then(|r| r.since_output(written - index))
+        {
+            Some(count) => self.rewind(count) == count,
+            None => false,
+        }
+    }
+
+    /// Gets the cells of the tape.
+    pub fn tape(&self) -> &[T] {
+        self.runner.cells()
+    }
+
+    /// Gets the cells of the tape mutably, so they can be changed between steps.
+    pub fn tape_mut(&mut self) -> &mut [T] {
+        self.runner.tape_mut().0
+    }
+
+    /// Gets the index of the current cell.
+    pub fn pointer(&self) -> usize {
+        self.runner.index()
+    }
+
+    /// Moves the pointer to a cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is past the end of the tape.
+    pub fn set_pointer(&mut self, index: usize) {
+        let (cells, pointer) = self.runner.tape_mut();
+
+        if index >= cells.len() {
+            panic!(
+                "cannot move the pointer to {index} on a tape of {} cells",
+                cells.len()
+            );
+        }
+
+        *pointer = index;
+    }
+
+    /// Gets the input which hasn't been read yet.
+    pub fn input(&self) -> &VecDeque<u8> {
+        self.runner.input()
+    }
+
+    /// Gets the input which hasn't been read yet mutably, so it can be changed between steps.
+    pub fn input_mut(&mut self) -> &mut VecDeque<u8> {
+        self.runner.input_mut()
+    }
+
+    /// Gets the output written so far.
+    pub fn output(&self) -> &[u8] {
+        self.runner.output()
+    }
+
+    /// Consumes the debugger, returning its runner.
+    pub fn into_runner(self) -> Runner<N, T> {
+        self.runner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints_on_the_first_operation_stop_before_it() {
+        let mut debugger = Debugger::<16>::new("#+++.", &[], RunnerOptions::default()).unwrap();
+
+        assert!(matches!(
+            debugger.run(),
+            Stop::Breakpoint { address: 0, .. }
+        ));
+        assert_eq!(debugger.steps(), 0);
+        assert_eq!(debugger.run(), Stop::Finished(Status::Halted));
+        assert_eq!(debugger.output(), [3]);
+    }
+
+    #[test]
+    fn breakpoints_at_the_end_of_a_loop_stop_each_iteration() {
+        let mut debugger = Debugger::<16>::new("++[-.#]+", &[], RunnerOptions::default()).unwrap();
+        let end = debugger.breakpoints().next().unwrap();
+
+        assert!(matches!(
+            debugger.bytecode().ops()[end],
+            Op::JumpIfNonzero(_)
+        ));
+
+        for written in 1..=2 {
+            assert_eq!(
+                debugger.run(),
+                Stop::Breakpoint {
+                    address: end,
+                    location: debugger.bytecode().location(end),
+                }
+            );
+            assert_eq!(debugger.output().len(), written);
+        }
+
+        assert_eq!(debugger.run(), Stop::Finished(Status::Halted));
+    }
+
+    #[test]
+    fn scans_take_a_step_for_each_cell() {
+        let source = "+>+>+>+<<<[>]";
+        let mut debugger = Debugger::<16>::new(source, &[], RunnerOptions::default()).unwrap();
+
+        // Eight operations set up the tape, and the scan takes a step to start.
+        for _ in 0..9 {
+            assert_eq!(debugger.step(), None);
+        }
+
+        // Then it moves past one cell per step, and the program ends once it finds a zero cell.
+        for position in 1..4 {
+            assert!(matches!(debugger.next_op(), Some(Op::Scan(1))));
+            assert_eq!(debugger.step(), None);
+            assert_eq!(debugger.runner().position(), position);
+        }
+
+        assert_eq!(debugger.step(), Some(Status::Halted));
+        assert_eq!(debugger.runner().position(), 4);
+
+        let fueled = Program::new(source)
+            .unwrap()
+            .run_with_fuel::<16, u8>(b"", 1_000);
+        assert_eq!(debugger.steps(), fueled.steps);
+    }
+
+    #[test]
+    fn endless_scans_can_be_stepped_through() {
+        let mut debugger = Debugger::<16>::new("+[[>]+]", &[], RunnerOptions::default()).unwrap();
+
+        for _ in 0..1_000 {
+            assert_eq!(debugger.step(), None);
+        }
+
+        assert_eq!(debugger.steps(), 1_000);
+        assert_eq!(
+            Program::new("+[[>]+]")
+                .unwrap()
+                .run_with_fuel::<16, u8>(b"", 1_000)
+                .runner
+                .position(),
+            debugger.runner().position()
+        );
+    }
+
+    #[test]
+    fn scans_can_be_stepped_back_over() {
+        let mut debugger =
+            Debugger::<16>::new("+>+>+<<[>]#.", &[], RunnerOptions::default()).unwrap();
+        debugger.record(true);
+
+        assert!(matches!(debugger.run(), Stop::Breakpoint { .. }));
+        assert_eq!(debugger.runner().position(), 3);
+
+        // Stepping back into the scan leaves it partway, and running again finishes it.
+        assert_eq!(debugger.rewind(2), 2);
+        assert_eq!(debugger.runner().position(), 1);
+        assert!(matches!(debugger.next_op(), Some(Op::Scan(1))));
+
+        assert!(matches!(debugger.run(), Stop::Breakpoint { .. }));
+        assert_eq!(debugger.runner().position(), 3);
+        assert_eq!(debugger.steps(), 10);
+    }
+}
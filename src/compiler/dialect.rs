@@ -0,0 +1,335 @@
+//! Front-ends and emitters for dialects which spell each Brainf*** command as a different token,
+//! such as Ook! and Blub.
+
+use super::{
+    error::{Location, ParseError, Span},
+    optimizer::optimize,
+    parse_commands, Program,
+};
+use std::{error::Error, fmt::Display};
+
+/// The Brainf*** commands, in the order a dialect's tokens are given in.
+const COMMANDS: [char; 8] = ['+', '-', '<', '>', ',', '.', '[', ']'];
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// An error returned when a dialect's tokens are ambiguous.
+pub enum DialectError {
+    /// The token for a command is empty or only contains whitespace.
+    EmptyToken(char),
+
+    /// Two commands have the same token.
+    DuplicateToken(char, char),
+}
+
+impl Display for DialectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DialectError::EmptyToken(command) => write!(f, "the token for `{command}` is empty"),
+
+            DialectError::DuplicateToken(first, second) => {
+                write!(f, "`{first}` and `{second}` have the same token")
+            }
+        }
+    }
+}
+
+impl Error for DialectError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A language which spells each Brainf*** command as a token.
+///
+/// Tokens may be made of several words, such as `Ook. Ook?`, in which case any amount of
+/// whitespace, including line breaks, can separate the words in source. Everything in source which
+/// isn't part of a token is a comment. Where several tokens match at once, the longest one wins.
+pub struct Dialect {
+    /// The words of each command's token, in the order `+-<>,.[]`.
+    tokens: [Vec<String>; 8],
+
+    /// The indices into `tokens`, ordered from the longest token to the shortest.
+    order: [usize; 8],
+
+    /// What to put between tokens when rendering source in this dialect.
+    separator: String,
+}
+
+impl Dialect {
+    /// Constructs a dialect from the token for each command, in the order `+-<>,.[]`. When rendering
+    /// source in this dialect, tokens are joined with `separator`, which should contain whitespace
+    /// if two tokens could otherwise run together into a different one. Returns an error if a
+    /// token is empty or two commands have the same token.
+    pub fn new(tokens: [&str; 8], separator: &str) -> Result<Self, DialectError> {
+        let tokens: [Vec<String>; 8] =
+            tokens.map(|token| token.split_whitespace().map(String::from).collect());
+
+        for (index, words) in tokens.iter().enumerate() {
+            if words.is_empty() {
+                return Err(DialectError::EmptyToken(COMMANDS[index]));
+            }
+
+            if let Some(other) = tokens[..index].iter().position(|other| other == words) {
+                return Err(DialectError::DuplicateToken(
+                    COMMANDS[other],
+                    COMMANDS[index],
+                ));
+            }
+        }
+
+        let mut order = [0, 1, 2, 3, 4, 5, 6, 7];
+        order.sort_by_key(|&index| {
+            let words = &tokens[index];
+            std::cmp::Reverse(words.iter().map(String::len).sum::<usize>() + words.len())
+        });
+
+        Ok(Self {
+            tokens,
+            order,
+            separator: separator.to_string(),
+        })
+    }
+
+    /// Gets plain Brainf***, where each command is its own token.
+    pub fn brainfuck() -> Self {
+        Self::new(["+", "-", "<", ">", ",", ".", "[", "]"], "").expect("the tokens are distinct")
+    }
+
+    /// Gets Ook!, where each command is a pair of `Ook.`, `Ook?` and `Ook!`.
+    pub fn ook() -> Self {
+        Self::pairs("Ook")
+    }
+
+    /// Gets Blub, which is Ook! with `Blub` in place of `Ook`.
+    pub fn blub() -> Self {
+        Self::pairs("Blub")
+    }
+
+    /// Gets a dialect in the style of Ook!, where each command is a pair of a word followed by
+    /// `.`, `?` or `!`.
+    fn pairs(word: &str) -> Self {
+        let tokens = [
+            ('.', '.'),
+            ('!', '!'),
+            ('?', '.'),
+            ('.', '?'),
+            ('.', '!'),
+            ('!', '.'),
+            ('!', '?'),
+            ('?', '!'),
+        ]
+        .map(|(first, second)| format!("{word}{first} {word}{second}"));
+
+        Self::new(tokens.each_ref().map(String::as_str), " ").expect("the tokens are distinct")
+    }
+
+    /// Gets the token for a Brainf*** command, or `None` if it isn't a command.
+    pub fn token(&self, command: char) -> Option<String> {
+        let index = COMMANDS.iter().position(|&other| other == command)?;
+        Some(self.tokens[index].join(" "))
+    }
+
+    /// Attempts to parse source written in this dialect, returning an error if it is malformed.
+    /// Locations in the program and in errors refer to the source in this dialect.
+    pub fn parse(&self, source: &str) -> Result<Program, ParseError> {
+        Ok(Program {
+            instructions: optimize(parse_commands(self.commands(source))?),
+            input: Vec::new(),
+        })
+    }
+
+    /// Translates source written in this dialect into Brainf***, dropping every comment.
+    pub fn translate(&self, source: &str) -> String {
+        self.commands(source).map(|(command, _)| command).collect()
+    }
+
+    /// Renders Brainf*** source, such as the source of a builder or a program's canonical source,
+    /// in this dialect. Every character which isn't a command is dropped.
+    pub fn render(&self, source: &str) -> String {
+        let mut output = String::new();
+
+        for command in source.chars() {
+            let Some(index) = COMMANDS.iter().position(|&other| other == command) else {
+                continue;
+            };
+
+            if !output.is_empty() {
+                output += &self.separator;
+            }
+
+            output += &self.tokens[index].join(" ");
+        }
+
+        output
+    }
+
+    /// Gets every command in source along with the span of its token.
+    fn commands<'a>(&'a self, source: &'a str) -> impl Iterator<Item = (char, Span)> + 'a {
+        let mut cursor = Cursor {
+            source,
+            location: Location {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+        };
+
+        std::iter::from_fn(move || {
+            while cursor.location.offset < source.len() {
+                let offset = cursor.location.offset;
+
+                let found = self.order.iter().find_map(|&index| {
+                    Some((index, matches(&self.tokens[index], &source[offset..])?))
+                });
+
+                let Some((index, length)) = found else {
+                    let char = source[offset..]
+                        .chars()
+                        .next()
+                        .expect("offset is in bounds");
+                    cursor.advance(offset + char.len_utf8());
+                    continue;
+                };
+
+                let start = cursor.location;
+                cursor.advance(offset + length);
+
+                return Some((
+                    COMMANDS[index],
+                    Span {
+                        start,
+                        end: cursor.location,
+                    },
+                ));
+            }
+
+            None
+        })
+    }
+}
+
+/// Checks if text starts with a token, returning the length of the match if it does.
+fn matches(words: &[String], text: &str) -> Option<usize> {
+    let mut length = 0;
+
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            let rest = &text[length..];
+            let trimmed = rest.trim_start();
+
+            // Words have to be separated by at least some whitespace.
+            if trimmed.len() == rest.len() {
+                return None;
+            }
+
+            length += rest.len() - trimmed.len();
+        }
+
+        if !text[length..].starts_with(word.as_str()) {
+            return None;
+        }
+
+        length += word.len();
+    }
+
+    Some(length)
+}
+
+/// A position in source which keeps track of lines and columns as it moves forward.
+struct Cursor<'a> {
+    source: &'a str,
+    location: Location,
+}
+
+impl Cursor<'_> {
+    /// Moves forward to a byte offset.
+    fn advance(&mut self, offset: usize) {
+        for char in self.source[self.location.offset..offset].chars() {
+            if char == '\n' {
+                self.location.line += 1;
+                self.location.column = 1;
+            } else {
+                self.location.column += 1;
+            }
+        }
+
+        self.location.offset = offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dialect, DialectError};
+    use crate::compiler::Program;
+
+    const HELLO_WORLD: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+    #[test]
+    fn rendered_source_parses_back() {
+        for dialect in [Dialect::ook(), Dialect::blub(), Dialect::brainfuck()] {
+            let rendered = dialect.render(HELLO_WORLD);
+            assert_eq!(dialect.translate(&rendered), HELLO_WORLD);
+
+            let program = dialect.parse(&rendered).unwrap();
+            let expected = Program::new(HELLO_WORLD).unwrap();
+
+            assert_eq!(program.to_string(), expected.to_string());
+            assert_eq!(
+                program.run::<64, u8>(b"").output(),
+                expected.run::<64, u8>(b"").output()
+            );
+        }
+    }
+
+    #[test]
+    fn words_of_a_token_can_be_split_by_any_whitespace() {
+        let ook = Dialect::ook();
+
+        assert_eq!(ook.token('+').unwrap(), "Ook. Ook.");
+        assert_eq!(ook.translate("Ook.\n\t Ook. Ook!\r\nOok!"), "+-");
+        assert_eq!(Dialect::blub().translate("Blub? Blub."), "<");
+    }
+
+    #[test]
+    fn invalid_pairs_are_comments() {
+        let ook = Dialect::ook();
+
+        // `Ook? Ook?` isn't a command, words have to be separated, and Ook! and Blub don't mix.
+        for source in ["Ook? Ook?", "Ook.Ook.", "Ook. Blub.", "Ook.", "ook. ook."] {
+            assert_eq!(ook.translate(source), "", "{source:?}");
+        }
+
+        assert_eq!(ook.translate("Ook. Ook. Ook? Ook?"), "+");
+        assert_eq!(ook.parse("Ook! Ook! Ook? Ook?").unwrap().to_string(), "-");
+    }
+
+    #[test]
+    fn unbalanced_loops_are_rejected() {
+        let ook = Dialect::ook();
+
+        assert!(ook.parse(&ook.render("+[-")).is_err());
+        assert!(ook.parse(&ook.render("+]-")).is_err());
+    }
+
+    #[test]
+    fn ambiguous_tokens_are_rejected() {
+        let mut tokens = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+        tokens[3] = " \n";
+        assert_eq!(
+            Dialect::new(tokens, " ").unwrap_err(),
+            DialectError::EmptyToken('>')
+        );
+
+        tokens[3] = "a";
+        assert_eq!(
+            Dialect::new(tokens, " ").unwrap_err(),
+            DialectError::DuplicateToken('+', '>')
+        );
+
+        // Tokens which only differ by whitespace are the same token.
+        tokens[3] = "x  y";
+        tokens[5] = "x y";
+        assert_eq!(
+            Dialect::new(tokens, " ").unwrap_err(),
+            DialectError::DuplicateToken('>', '.')
+        );
+    }
+}
@@ -0,0 +1,564 @@
+//! A compact binary encoding of programs, so compiled programs can be cached and loaded again
+//! without reparsing their source.
+//!
+//! The encoding starts with a header and the program's embedded input, followed by every node in
+//! source order. Each node is a tag byte, its span, and the values of its instruction. The bodies
+//! of loops and procedures follow the node they belong to and end with an `END` tag. Numbers are
+//! variable-length, and spans are stored relative to where the previous node left off, so a node
+//! parsed from source usually takes two or three bytes.
+
+use super::{
+    ast::{Instruction, Node},
+    error::{Location, Span},
+    Program,
+};
+use std::{error::Error, fmt::Display};
+
+/// The bytes every encoded program starts with.
+const MAGIC: &[u8; 4] = b"BFIR";
+
+/// The version of the encoding, which changes whenever old programs can no longer be decoded.
+const VERSION: u8 = 1;
+
+const END: u8 = 0;
+const ADD: u8 = 1;
+const MOVE: u8 = 2;
+const READ: u8 = 3;
+const WRITE: u8 = 4;
+const LOOP: u8 = 5;
+const CLEAR: u8 = 6;
+const MUL_ADD: u8 = 7;
+const SCAN: u8 = 8;
+const DUMP: u8 = 9;
+const PROCEDURE: u8 = 10;
+const CALL: u8 = 11;
+
+/// Set on a tag when the node starts where the previous one left off, which is right after the
+/// previous node or right after the `[` or `(` of the loop or procedure the node is the first in.
+const CONTIGUOUS: u8 = 0x80;
+
+/// Set on a tag when the node ends on the line it starts on, one column further per byte.
+const SINGLE_LINE: u8 = 0x40;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// An error returned when encoded data isn't a valid program.
+pub enum DecodeError {
+    /// The data doesn't start with the header of an encoded program.
+    InvalidHeader,
+
+    /// The data was encoded with an incompatible version of the encoding.
+    UnsupportedVersion(u8),
+
+    /// The data ended in the middle of a node, or before every loop was closed.
+    UnexpectedEnd,
+
+    /// An unknown tag was found at a byte offset.
+    InvalidTag {
+        /// The byte offset of the tag.
+        offset: usize,
+
+        /// The tag which was found.
+        tag: u8,
+    },
+
+    /// The end of a loop was found at a byte offset outside of any loop.
+    UnmatchedEnd(usize),
+
+    /// A number at a byte offset is too large for the value it encodes.
+    Overflow(usize),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidHeader => write!(f, "data isn't an encoded program"),
+
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported encoding version {version}")
+            }
+
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of data"),
+
+            DecodeError::InvalidTag { offset, tag } => {
+                write!(f, "invalid tag {tag} at byte {offset}")
+            }
+
+            DecodeError::UnmatchedEnd(offset) => {
+                write!(f, "unmatched end of loop at byte {offset}")
+            }
+
+            DecodeError::Overflow(offset) => write!(f, "number too large at byte {offset}"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl Program {
+    /// Encodes this program into bytes, which `Program::from_bytes` decodes back into the same
+    /// program, spans included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+
+        write_unsigned(&mut bytes, self.input.len() as u64);
+        bytes.extend(&self.input);
+
+        let mut cursor = START;
+
+        // Each entry holds the remaining nodes of a list and the span of the node it is the body
+        // of.
+        let mut stack = vec![(self.instructions.iter(), None)];
+
+        while let Some((nodes, parent)) = stack.last_mut() {
+            let Some(node) = nodes.next() else {
+                if let Some(Span { end, .. }) = *parent {
+                    bytes.push(END);
+                    cursor = end;
+                }
+
+                stack.pop();
+                continue;
+            };
+
+            let tag = match &node.instruction {
+                Instruction::Add(_) => ADD,
+                Instruction::Move(_) => MOVE,
+                Instruction::Read => READ,
+                Instruction::Write => WRITE,
+                Instruction::Loop(_) => LOOP,
+                Instruction::Clear => CLEAR,
+                Instruction::MulAdd(_) => MUL_ADD,
+                Instruction::Scan(_) => SCAN,
+                Instruction::Dump => DUMP,
+                Instruction::Procedure(_) => PROCEDURE,
+                Instruction::Call => CALL,
+            };
+
+            write_span(&mut bytes, tag, node.span, cursor);
+            cursor = node.span.end;
+
+            match &node.instruction {
+                Instruction::Add(value) => write_signed(&mut bytes, *value as i64),
+                Instruction::Move(offset) | Instruction::Scan(offset) => {
+                    write_signed(&mut bytes, *offset as i64)
+                }
+
+                Instruction::MulAdd(targets) => {
+                    write_unsigned(&mut bytes, targets.len() as u64);
+
+                    for (offset, factor) in targets {
+                        write_signed(&mut bytes, *offset as i64);
+                        write_signed(&mut bytes, *factor as i64);
+                    }
+                }
+
+                Instruction::Loop(body) | Instruction::Procedure(body) => {
+                    cursor = after_bracket(node.span.start);
+                    stack.push((body.iter(), Some(node.span)));
+                }
+
+                Instruction::Read
+                | Instruction::Write
+                | Instruction::Clear
+                | Instruction::Dump
+                | Instruction::Call => {}
+            }
+        }
+
+        bytes
+    }
+
+    /// Decodes a program encoded by `Program::to_bytes`, returning an error if the data isn't a
+    /// valid program.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        match bytes.split_at_checked(MAGIC.len()) {
+            Some((magic, [version, ..])) if magic == MAGIC => {
+                if *version != VERSION {
+                    return Err(DecodeError::UnsupportedVersion(*version));
+                }
+            }
+
+            _ => return Err(DecodeError::InvalidHeader),
+        }
+
+        let mut reader = Reader {
+            bytes,
+            position: MAGIC.len() + 1,
+        };
+
+        let length: usize = reader.unsigned()?;
+        let input = reader.bytes(length)?.to_vec();
+
+        let mut cursor = START;
+
+        // Each entry holds the nodes of a list decoded so far, and the span of the node the list
+        // is the body of along with whether it is a procedure.
+        let mut stack = vec![(Vec::new(), None)];
+
+        while reader.position < bytes.len() {
+            let offset = reader.position;
+            let tag = reader.byte()?;
+
+            if tag == END {
+                let (body, parent) = stack.pop().expect("the stack is never empty here");
+
+                match (stack.last_mut(), parent) {
+                    (Some((parent, _)), Some((span, procedure))) => {
+                        let instruction = if procedure {
+                            Instruction::Procedure(body)
+                        } else {
+                            Instruction::Loop(body)
+                        };
+
+                        parent.push(Node { instruction, span });
+
+                        cursor = span.end;
+                    }
+
+                    _ => return Err(DecodeError::UnmatchedEnd(offset)),
+                }
+
+                continue;
+            }
+
+            let kind = tag & !(CONTIGUOUS | SINGLE_LINE);
+
+            if !(ADD..=CALL).contains(&kind) {
+                return Err(DecodeError::InvalidTag { offset, tag });
+            }
+
+            let span = reader.span(tag, cursor)?;
+            cursor = span.end;
+
+            let instruction = match kind {
+                ADD => Instruction::Add(reader.signed()?),
+                MOVE => Instruction::Move(reader.signed()?),
+                READ => Instruction::Read,
+                WRITE => Instruction::Write,
+                CLEAR => Instruction::Clear,
+                SCAN => Instruction::Scan(reader.signed()?),
+                DUMP => Instruction::Dump,
+                CALL => Instruction::Call,
+
+                MUL_ADD => {
+                    let count: usize = reader.unsigned()?;
+
+                    // Each target takes at least two bytes, which stops a corrupt count from
+                    // allocating more than the data could hold.
+                    let mut targets = Vec::with_capacity(count.min(bytes.len() / 2));
+
+                    for _ in 0..count {
+                        targets.push((reader.signed()?, reader.signed()?));
+                    }
+
+                    Instruction::MulAdd(targets)
+                }
+
+                LOOP | PROCEDURE => {
+                    cursor = after_bracket(span.start);
+                    stack.push((Vec::new(), Some((span, kind == PROCEDURE))));
+                    continue;
+                }
+
+                _ => unreachable!("tags were checked above"),
+            };
+
+            let (nodes, _) = stack.last_mut().expect("the stack is never empty here");
+            nodes.push(Node { instruction, span });
+        }
+
+        match stack.pop() {
+            Some((instructions, None)) => Ok(Program {
+                instructions,
+                input,
+            }),
+            _ => Err(DecodeError::UnexpectedEnd),
+        }
+    }
+}
+
+/// The location of the first character of any source.
+const START: Location = Location {
+    offset: 0,
+    line: 1,
+    column: 1,
+};
+
+/// Gets the location right after the `[` or `(` of a loop or procedure starting at a location.
+fn after_bracket(start: Location) -> Location {
+    Location {
+        offset: start.offset + 1,
+        line: start.line,
+        column: start.column + 1,
+    }
+}
+
+/// Writes a number using as few bytes as possible, with seven bits per byte and the high bit set
+/// on every byte but the last.
+fn write_unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+/// Writes a number which may be negative, interleaving negative and positive values so numbers
+/// close to zero stay short.
+fn write_signed(bytes: &mut Vec<u8>, value: i64) {
+    write_unsigned(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+/// Writes the tag of a node along with its span, relative to where the previous node left off.
+/// Only what the flags on the tag can't describe is written. Columns are written as is when the
+/// line changes, since they start over on every line.
+fn write_span(bytes: &mut Vec<u8>, tag: u8, span: Span, cursor: Location) {
+    let Span { start, end } = span;
+    let length = end.offset as i64 - start.offset as i64;
+
+    let contiguous = start == cursor;
+    let single_line = end.line == start.line && end.column as i64 - start.column as i64 == length;
+
+    bytes.push(
+        tag | if contiguous { CONTIGUOUS } else { 0 } | if single_line { SINGLE_LINE } else { 0 },
+    );
+
+    if !contiguous {
+        write_signed(bytes, start.offset as i64 - cursor.offset as i64);
+        write_signed(bytes, start.line as i64 - cursor.line as i64);
+        write_unsigned(bytes, start.column as u64);
+    }
+
+    write_signed(bytes, length);
+
+    if !single_line {
+        write_signed(bytes, end.line as i64 - start.line as i64);
+        write_unsigned(bytes, end.column as u64);
+    }
+}
+
+/// Adds a difference read at a byte offset to a number, which can't go negative.
+fn shift(base: usize, delta: i64, offset: usize) -> Result<usize, DecodeError> {
+    (base as i64)
+        .checked_add(delta)
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or(DecodeError::Overflow(offset))
+}
+
+/// Reads encoded data from front to back.
+struct Reader<'a> {
+    bytes: &'a [u8],
+
+    /// The offset of the next byte to read.
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+
+        self.position += 1;
+
+        Ok(byte)
+    }
+
+    /// Reads a number of bytes as they are.
+    fn bytes(&mut self, count: usize) -> Result<&[u8], DecodeError> {
+        let bytes = self
+            .bytes
+            .get(self.position..)
+            .and_then(|rest| rest.get(..count))
+            .ok_or(DecodeError::UnexpectedEnd)?;
+
+        self.position += count;
+
+        Ok(bytes)
+    }
+
+    /// Reads a number written by `write_unsigned` and converts it to the type it encodes.
+    fn unsigned<U: TryFrom<u64>>(&mut self) -> Result<U, DecodeError> {
+        let start = self.position;
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+
+            if shift >= 64 || (shift > 0 && bits >> (64 - shift) != 0) {
+                return Err(DecodeError::Overflow(start));
+            }
+
+            value |= bits << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        U::try_from(value).map_err(|_| DecodeError::Overflow(start))
+    }
+
+    /// Reads a number written by `write_signed` and converts it to the type it encodes.
+    fn signed<S: TryFrom<i64>>(&mut self) -> Result<S, DecodeError> {
+        let start = self.position;
+        let value: u64 = self.unsigned()?;
+        let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+
+        S::try_from(value).map_err(|_| DecodeError::Overflow(start))
+    }
+
+    /// Reads a number written by `write_signed` and adds it to another.
+    fn relative(&mut self, base: usize) -> Result<usize, DecodeError> {
+        let offset = self.position;
+        shift(base, self.signed()?, offset)
+    }
+
+    /// Reads the span of a node written by `write_span`, after its tag.
+    fn span(&mut self, tag: u8, cursor: Location) -> Result<Span, DecodeError> {
+        let start = if tag & CONTIGUOUS != 0 {
+            cursor
+        } else {
+            Location {
+                offset: self.relative(cursor.offset)?,
+                line: self.relative(cursor.line)?,
+                column: self.unsigned()?,
+            }
+        };
+
+        let offset = self.position;
+        let length = self.signed()?;
+
+        let end = if tag & SINGLE_LINE != 0 {
+            Location {
+                offset: shift(start.offset, length, offset)?,
+                line: start.line,
+                column: shift(start.column, length, offset)?,
+            }
+        } else {
+            Location {
+                offset: shift(start.offset, length, offset)?,
+                line: self.relative(start.line)?,
+                column: self.unsigned()?,
+            }
+        };
+
+        Ok(Span { start, end })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, MAGIC, VERSION};
+    use crate::compiler::{ParseOptions, Program};
+
+    const EXTENDED: ParseOptions = ParseOptions {
+        dump: true,
+        embedded_input: true,
+        procedures: true,
+    };
+
+    const SOURCES: &[&str] = &[
+        "",
+        "+++.",
+        "comments between +\n  commands >>\n\n<-,",
+        "++[->+>---<<]>[<]>>[-]#",
+        "+(>++++[-<+>]<.):-!embedded input",
+        "[[[]]]\n[\n\t>\n]",
+    ];
+
+    /// Encodes a program, checking that it decodes back into the same program.
+    fn round_trip(program: &Program) -> Vec<u8> {
+        let bytes = program.to_bytes();
+        let decoded = Program::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.nodes(), program.nodes());
+        assert_eq!(decoded.input(), program.input());
+
+        bytes
+    }
+
+    #[test]
+    fn programs_round_trip() {
+        for source in SOURCES {
+            round_trip(&Program::with_options(source, EXTENDED).unwrap());
+        }
+
+        // Programs from a syntax tree can have spans which source never would.
+        let program = Program::with_options(SOURCES[4], EXTENDED).unwrap();
+        let mut nodes = program.into_nodes();
+        nodes.reverse();
+        round_trip(&Program::from_nodes(nodes));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let mut bytes = Program::new("+.").unwrap().to_bytes();
+        assert_eq!(bytes[MAGIC.len()], VERSION);
+
+        bytes[MAGIC.len()] = VERSION + 1;
+        assert_eq!(
+            Program::from_bytes(&bytes).unwrap_err(),
+            DecodeError::UnsupportedVersion(VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn other_data_is_rejected() {
+        for bytes in [&b""[..], b"BFI", b"BFIR", b"RIFB\x01\x00", b"+++."] {
+            assert_eq!(
+                Program::from_bytes(bytes).unwrap_err(),
+                DecodeError::InvalidHeader
+            );
+        }
+
+        let mut bytes = Program::new("+.").unwrap().to_bytes();
+        let end = bytes.len();
+
+        bytes.push(0x3f);
+        assert_eq!(
+            Program::from_bytes(&bytes).unwrap_err(),
+            DecodeError::InvalidTag {
+                offset: end,
+                tag: 0x3f
+            }
+        );
+
+        bytes[end] = 0;
+        assert_eq!(
+            Program::from_bytes(&bytes).unwrap_err(),
+            DecodeError::UnmatchedEnd(end)
+        );
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let program = Program::with_options("+[->>+<<]>[.(,)[-]<]!input", EXTENDED).unwrap();
+        let bytes = round_trip(&program);
+
+        // Data which stops between top-level nodes is a shorter program, but anything else is cut
+        // off partway through the input, a node or a loop.
+        for length in MAGIC.len() + 1..bytes.len() {
+            match Program::from_bytes(&bytes[..length]) {
+                Ok(decoded) => {
+                    assert_eq!(decoded.input(), program.input());
+                    assert!(program.nodes().starts_with(decoded.nodes()));
+                    assert!(decoded.nodes().len() < program.nodes().len());
+                }
+
+                Err(error) => assert_eq!(error, DecodeError::UnexpectedEnd, "{length}"),
+            }
+        }
+
+        assert_eq!(
+            Program::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Errors that can occur while parsing a Brainf*** program.
+
+use std::{error::Error, fmt::Display};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// A position in a program's source.
+pub struct Location {
+    /// The byte offset of this position from the start of the source.
+    pub offset: usize,
+
+    /// The line of this position, starting at 1.
+    pub line: usize,
+
+    /// The column of this position in characters, starting at 1.
+    pub column: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// A range of a program's source.
+pub struct Span {
+    /// The position of the first character in the range.
+    pub start: Location,
+
+    /// The position right after the last character in the range.
+    pub end: Location,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// An error returned when a program's source is malformed.
+pub enum ParseError {
+    /// A `]` was found without a matching `[`, or likewise a `)` without a matching `(` when
+    /// parsing procedures.
+    UnmatchedClosingBracket(Location),
+
+    /// One or more `[`, or `(` when parsing procedures, were never closed. The locations are
+    /// ordered from the outermost bracket to the innermost one, and there is always at least one of
+    /// them.
+    UnmatchedOpeningBracket(Vec<Location>),
+}
+
+impl ParseError {
+    /// Gets the location of the first bracket responsible for this error.
+    pub fn location(&self) -> Location {
+        match self {
+            ParseError::UnmatchedClosingBracket(location) => *location,
+            ParseError::UnmatchedOpeningBracket(locations) => locations[0],
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnmatchedClosingBracket(location) => {
+                write!(f, "unmatched closing bracket at {location}")
+            }
+
+            ParseError::UnmatchedOpeningBracket(locations) => {
+                if locations.len() == 1 {
+                    write!(f, "unmatched opening bracket at {}", locations[0])
+                } else {
+                    write!(
+                        f,
+                        "unmatched opening brackets at {}",
+                        locations
+                            .iter()
+                            .map(|location| location.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
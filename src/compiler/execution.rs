@@ -0,0 +1,122 @@
+//! Limits which stop a program early, and the outcome of running a program under them.
+
+use super::error::Location;
+use crate::runner::{cell::CellValue, Runner};
+use std::{collections::VecDeque, io::ErrorKind, time::Instant};
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// Limits on how long a program may run. The default value imposes no limits.
+pub struct Limit {
+    /// The maximum number of steps to take. Each bytecode operation is a step, and a scan takes
+    /// another step for each cell it moves past, since it can run for as long as the loop it
+    /// replaced. Clears and multiply-adds are a single step, since their work doesn't depend on
+    /// the tape.
+    pub fuel: Option<u64>,
+
+    /// A point in time after which the program is stopped. This is only checked every few thousand
+    /// operations, so a program may run slightly past it.
+    pub deadline: Option<Instant>,
+}
+
+impl Limit {
+    /// Constructs a limit on the number of operations executed.
+    pub fn fuel(fuel: u64) -> Self {
+        Self {
+            fuel: Some(fuel),
+            deadline: None,
+        }
+    }
+
+    /// Constructs a limit on wall-clock time.
+    pub fn deadline(deadline: Instant) -> Self {
+        Self {
+            fuel: None,
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Checks if a program which has taken a number of steps has to stop, returning why if it
+    /// does. The deadline is only checked every `DEADLINE_CHECK_INTERVAL` steps.
+    pub(crate) fn exceeded(&self, steps: u64) -> Option<Status> {
+        if self.fuel.is_some_and(|fuel| steps >= fuel) {
+            return Some(Status::OutOfFuel);
+        }
+
+        if steps.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Some(Status::DeadlineExceeded);
+        }
+
+        None
+    }
+}
+
+/// How many steps are taken between checks of the deadline, since getting the current time is
+/// much slower than executing an operation.
+const DEADLINE_CHECK_INTERVAL: u64 = 4096;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// The reason a program stopped running.
+pub enum Status {
+    /// The program ran to completion.
+    Halted,
+
+    /// The program used up all of its fuel before completing.
+    OutOfFuel,
+
+    /// The program was still running when its deadline passed.
+    DeadlineExceeded,
+
+    /// The pointer moved past the end of a tape whose policy doesn't allow it.
+    OutOfBounds {
+        /// The location of the command which moved the pointer.
+        location: Location,
+
+        /// The index the pointer tried to move to.
+        index: isize,
+    },
+
+    /// Arithmetic took a cell out of range under `Overflow::Error`.
+    Overflow {
+        /// The location of the command which overflowed the cell.
+        location: Location,
+
+        /// The index of the cell which overflowed.
+        index: usize,
+    },
+
+    /// Reading from input or writing to output failed.
+    Io(ErrorKind),
+
+    /// A procedure was called which hadn't been defined.
+    UndefinedProcedure {
+        /// The location of the command which called the procedure.
+        location: Location,
+
+        /// The value of the current cell, which identifies the procedure.
+        id: u32,
+    },
+}
+
+#[derive(Clone, Debug)]
+/// The result of running a program under a `Limit`.
+pub struct Outcome<const N: usize, T: CellValue = u8, I = VecDeque<u8>, O = Vec<u8>> {
+    /// Why the program stopped.
+    pub status: Status,
+
+    /// The number of steps which were taken, as counted by `Limit::fuel`.
+    pub steps: u64,
+
+    /// The state of the runner when the program stopped, which is partial unless it halted.
+    pub runner: Runner<N, T, I, O>,
+}
+
+impl<const N: usize, T: CellValue, I, O> Outcome<N, T, I, O> {
+    /// Checks if the program ran to completion.
+    pub fn halted(&self) -> bool {
+        self.status == Status::Halted
+    }
+}
@@ -0,0 +1,438 @@
+//! Formats Brainf*** source for reading, and renders programs back into canonical source.
+
+use super::{error::ParseError, parse, Instruction, Program};
+use std::fmt::{self, Display, Write};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// Options which change how source is formatted.
+pub struct FormatOptions {
+    /// The number of spaces to indent each level of loop nesting by.
+    pub indent: usize,
+
+    /// The maximum length of a line, including its indentation. Lines only go over it when their
+    /// indentation alone leaves no room, or when a single comment word is longer than it.
+    pub width: usize,
+
+    /// Whether to keep characters which aren't commands. Whitespace is always reflowed, so comments
+    /// are kept as words separated by single spaces.
+    pub comments: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            width: 80,
+            comments: true,
+        }
+    }
+}
+
+/// A piece of source, grouped by loops.
+enum Item {
+    /// A run of commands other than brackets.
+    Commands(String),
+
+    /// A word of comment characters.
+    Comment(String),
+
+    /// A loop along with its body.
+    Loop(Vec<Item>),
+}
+
+/// Formats source so each loop which doesn't fit on one line, or which contains other loops, has
+/// its body on separate lines, indented by its depth. Lines are wrapped at the configured width.
+/// Returns an error if the source is malformed.
+pub fn format(source: &str, options: FormatOptions) -> Result<String, ParseError> {
+    parse(source)?;
+
+    let mut formatter = Formatter {
+        options,
+        output: String::new(),
+        line: String::new(),
+        depth: 0,
+        comment: false,
+    };
+
+    // Each entry holds the remaining items of a list. Every list but the outermost is the body of
+    // a loop which was put on its own lines, so it is closed once it runs out.
+    let items = items(source, options.comments);
+    let mut stack = vec![items.iter()];
+
+    while let Some(items) = stack.last_mut() {
+        let Some(item) = items.next() else {
+            stack.pop();
+
+            if !stack.is_empty() {
+                formatter.close();
+            }
+
+            continue;
+        };
+
+        match item {
+            Item::Commands(commands) => formatter.commands(commands),
+            Item::Comment(comment) => formatter.atom(comment, true),
+
+            Item::Loop(body) => match formatter.inline(body) {
+                Some(inline) => formatter.atom(&inline, false),
+
+                None => {
+                    formatter.open();
+                    stack.push(body.iter());
+                }
+            },
+        }
+    }
+
+    formatter.flush();
+
+    Ok(formatter.output)
+}
+
+/// Splits source into commands and comment words, grouped by loops. The source must be balanced.
+fn items(source: &str, comments: bool) -> Vec<Item> {
+    let mut lists = Vec::new();
+    let mut current = Vec::new();
+
+    // Whether the last character was part of a comment word.
+    let mut word = false;
+
+    for char in source.chars() {
+        match char {
+            '[' => lists.push(std::mem::take(&mut current)),
+
+            ']' => {
+                let body = current;
+                current = lists.pop().expect("source is balanced");
+                current.push(Item::Loop(body));
+            }
+
+            '+' | '-' | '<' | '>' | ',' | '.' => match current.last_mut() {
+                Some(Item::Commands(commands)) => commands.push(char),
+                _ => current.push(Item::Commands(char.into())),
+            },
+
+            _ if !comments || char.is_whitespace() => {}
+
+            _ => match current.last_mut() {
+                Some(Item::Comment(comment)) if word => comment.push(char),
+                _ => current.push(Item::Comment(char.into())),
+            },
+        }
+
+        word = comments && !char.is_whitespace() && !"+-<>,.[]".contains(char);
+    }
+
+    current
+}
+
+/// Writes formatted lines.
+struct Formatter {
+    options: FormatOptions,
+    output: String,
+
+    /// The line being built, without its indentation.
+    line: String,
+
+    /// The number of loops which have been put on their own lines and not yet closed.
+    depth: usize,
+
+    /// Whether the last piece of the line is a comment word, which needs a space after it.
+    comment: bool,
+}
+
+impl Formatter {
+    /// Gets the number of characters which still fit on the current line.
+    fn room(&self) -> usize {
+        self.options
+            .width
+            .saturating_sub(self.depth * self.options.indent + self.line.chars().count())
+    }
+
+    /// Ends the current line, if it has anything on it.
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let indent = " ".repeat(self.depth * self.options.indent);
+            writeln!(self.output, "{indent}{}", self.line).unwrap();
+            self.line.clear();
+        }
+
+        self.comment = false;
+    }
+
+    /// Adds a piece of source which can't be split across lines.
+    fn atom(&mut self, text: &str, comment: bool) {
+        let space = !self.line.is_empty() && (comment || self.comment);
+
+        if !self.line.is_empty() && text.chars().count() + space as usize > self.room() {
+            self.flush();
+        }
+
+        if space && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+
+        self.line += text;
+        self.comment = comment;
+    }
+
+    /// Adds commands, which are split across lines wherever they run out of room.
+    fn commands(&mut self, commands: &str) {
+        if self.comment {
+            self.line.push(' ');
+            self.comment = false;
+        }
+
+        for command in commands.chars() {
+            if !self.line.is_empty() && self.room() == 0 {
+                self.flush();
+            }
+
+            self.line.push(command);
+        }
+    }
+
+    /// Renders a loop on a single line if it contains no other loops and fits on an empty line.
+    fn inline(&self, body: &[Item]) -> Option<String> {
+        let mut inline = String::from("[");
+        let mut comment = false;
+
+        for item in body {
+            match item {
+                Item::Commands(commands) => {
+                    if comment {
+                        inline.push(' ');
+                    }
+
+                    inline += commands;
+                    comment = false;
+                }
+
+                Item::Comment(text) => {
+                    if inline.len() > 1 {
+                        inline.push(' ');
+                    }
+
+                    inline += text;
+                    comment = true;
+                }
+
+                Item::Loop(_) => return None,
+            }
+        }
+
+        inline.push(']');
+
+        let room = self
+            .options
+            .width
+            .saturating_sub(self.depth * self.options.indent);
+
+        (inline.chars().count() <= room).then_some(inline)
+    }
+
+    /// Opens a loop whose body goes on its own lines.
+    fn open(&mut self) {
+        self.flush();
+        self.line.push('[');
+        self.flush();
+        self.depth += 1;
+    }
+
+    /// Closes a loop opened by `open`.
+    fn close(&mut self) {
+        self.flush();
+        self.depth -= 1;
+        self.line.push(']');
+        self.flush();
+    }
+}
+
+impl Display for Program {
+    /// Renders the program as canonical source, which contains only commands and parses back into
+    /// the same program. Recognised loop idioms are written out as the loops they stand for.
+    ///
+    /// Programs which use extended commands are rendered with them, followed by `!` and their
+    /// embedded input if they have any, so they parse back with the same `ParseOptions`. Input
+    /// which isn't valid UTF-8 is rendered lossily.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn repeat(f: &mut fmt::Formatter<'_>, value: i64, up: char, down: char) -> fmt::Result {
+            let command = if value > 0 { up } else { down };
+
+            for _ in 0..value.unsigned_abs() {
+                f.write_char(command)?;
+            }
+
+            Ok(())
+        }
+
+        // Each entry holds the remaining instructions of a list and the bracket which closes it, if
+        // it is the body of a loop or procedure.
+        let mut stack = vec![(self.instructions.iter(), None)];
+
+        while let Some((instructions, closing)) = stack.last_mut() {
+            let Some(node) = instructions.next() else {
+                if let Some(closing) = *closing {
+                    f.write_char(closing)?;
+                }
+
+                stack.pop();
+                continue;
+            };
+
+            match &node.instruction {
+                Instruction::Add(value) => repeat(f, *value as i64, '+', '-')?,
+                Instruction::Move(offset) => repeat(f, *offset as i64, '>', '<')?,
+                Instruction::Read => f.write_char(',')?,
+                Instruction::Write => f.write_char('.')?,
+                Instruction::Clear => f.write_str("[-]")?,
+
+                Instruction::MulAdd(targets) => {
+                    f.write_str("[-")?;
+
+                    let mut position = 0;
+
+                    for (offset, factor) in targets {
+                        repeat(f, (offset - position) as i64, '>', '<')?;
+                        repeat(f, *factor as i64, '+', '-')?;
+                        position = *offset;
+                    }
+
+                    repeat(f, -position as i64, '>', '<')?;
+                    f.write_char(']')?;
+                }
+
+                Instruction::Scan(step) => {
+                    f.write_char('[')?;
+                    repeat(f, *step as i64, '>', '<')?;
+                    f.write_char(']')?;
+                }
+
+                Instruction::Loop(body) => {
+                    f.write_char('[')?;
+                    stack.push((body.iter(), Some(']')));
+                }
+
+                Instruction::Dump => f.write_char('#')?,
+                Instruction::Call => f.write_char(':')?,
+
+                Instruction::Procedure(body) => {
+                    f.write_char('(')?;
+                    stack.push((body.iter(), Some(')')));
+                }
+            }
+        }
+
+        if !self.input.is_empty() {
+            write!(f, "!{}", String::from_utf8_lossy(&self.input))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, FormatOptions};
+    use crate::compiler::{ParseOptions, Program};
+
+    const HELLO_WORLD: &str = "Prints hello world ++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++. and stops";
+
+    const SOURCES: &[&str] = &[
+        HELLO_WORLD,
+        "",
+        "just a comment",
+        "+[-[>+<-]>[<+>-]<]",
+        "[[[[[[]]]]]] deeply nested and empty",
+        "+++ set up [> move right +++++ add five < back - decrement ] done .",
+        "naïve café comments ,[.,] échoes",
+    ];
+
+    fn narrow() -> FormatOptions {
+        FormatOptions {
+            indent: 4,
+            width: 12,
+            comments: true,
+        }
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        for options in [FormatOptions::default(), narrow()] {
+            for source in SOURCES {
+                let formatted = format(source, options).unwrap();
+                assert_eq!(
+                    format(&formatted, options).unwrap(),
+                    formatted,
+                    "{source:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn formatting_preserves_behaviour() {
+        let stripped = FormatOptions {
+            comments: false,
+            ..FormatOptions::default()
+        };
+
+        for options in [FormatOptions::default(), narrow(), stripped] {
+            for source in SOURCES {
+                let formatted = format(source, options).unwrap();
+                let expected = Program::new(source).unwrap().run::<64, u8>(b"input");
+                let actual = Program::new(&formatted).unwrap().run::<64, u8>(b"input");
+
+                assert_eq!(actual.output(), expected.output(), "{formatted:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn lines_are_measured_in_characters() {
+        let options = FormatOptions {
+            width: 8,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(format("éé éé éé", options).unwrap(), "éé éé éé\n");
+        assert_eq!(format("éé éé éé é", options).unwrap(), "éé éé éé\né\n");
+        assert_eq!(format("+[é é é]", options).unwrap(), "+[é é é]\n");
+    }
+
+    #[test]
+    fn rendering_round_trips() {
+        for source in SOURCES {
+            let program = Program::new(source).unwrap();
+            let rendered = program.to_string();
+            let reparsed = Program::new(&rendered).unwrap();
+
+            assert_eq!(reparsed.to_string(), rendered);
+            assert_eq!(
+                reparsed.run::<64, u8>(b"input").output(),
+                program.run::<64, u8>(b"input").output()
+            );
+        }
+    }
+
+    #[test]
+    fn rendering_keeps_extended_commands_and_input() {
+        let options = ParseOptions {
+            dump: true,
+            embedded_input: true,
+            procedures: true,
+        };
+
+        let program = Program::with_options("+(,.)# calls : twice :!hi", options).unwrap();
+        let rendered = program.to_string();
+
+        assert_eq!(rendered, "+(,.)#::!hi");
+        assert_eq!(
+            Program::with_options(&rendered, options)
+                .unwrap()
+                .to_string(),
+            rendered
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! A just-in-time compiler which turns programs into x86-64 machine code on Linux.
+//!
+//! Compiled code works directly on a runner's tape and calls back into the runner for input and
+//! output, so it behaves exactly like the bytecode interpreter while running many times faster.
+//!
+//! Besides the registers used by every x86-64 backend, compiled code keeps the context passed to
+//! callbacks in `r14`, the read callback in `r15` and the write callback in `rbp`.
+
+use super::{
+    backend::x86_64::{Assembler, Environment},
+    bytecode::Bytecode,
+    execution::{Limit, Status},
+    Program,
+};
+use crate::runner::{
+    cell::{CellValue, Overflow},
+    tape::TapePolicy,
+    Runner,
+};
+use std::{
+    ffi::c_void,
+    io::{self, ErrorKind, Read, Write},
+    marker::PhantomData,
+    ptr,
+};
+
+const PROT_READ: i32 = 1;
+const PROT_WRITE: i32 = 2;
+const PROT_EXEC: i32 = 4;
+const MAP_PRIVATE: i32 = 2;
+const MAP_ANONYMOUS: i32 = 0x20;
+
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: i32,
+        flags: i32,
+        fd: i32,
+        offset: i64,
+    ) -> *mut c_void;
+    fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+}
+
+/// The signature of compiled code. It takes the address of the first cell, the callback context,
+/// the index of the current cell and the read and write callbacks, and returns the index of the
+/// current cell once it stops.
+type Entry = unsafe extern "C" fn(*mut c_void, *mut c_void, usize, usize, usize) -> usize;
+
+/// A mapping of executable memory which is unmapped when dropped.
+struct ExecutableMemory {
+    address: *mut c_void,
+    len: usize,
+}
+
+impl ExecutableMemory {
+    /// Maps machine code into memory which can be executed but no longer written.
+    fn new(code: &[u8]) -> io::Result<Self> {
+        let len = code.len();
+
+        // SAFETY: Mapping fresh anonymous memory doesn't touch any existing memory.
+        let address = unsafe {
+            mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if address as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let memory = Self { address, len };
+
+        // SAFETY: The mapping is writable and at least `len` bytes long.
+        unsafe {
+            ptr::copy_nonoverlapping(code.as_ptr(), address.cast(), len);
+        }
+
+        // SAFETY: The mapping is owned by `memory` and nothing else refers to it.
+        if unsafe { mprotect(address, len, PROT_READ | PROT_EXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(memory)
+    }
+}
+
+impl Drop for ExecutableMemory {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created by `new` and is no longer referenced.
+        unsafe {
+            munmap(self.address, self.len);
+        }
+    }
+}
+
+/// The state shared with callbacks while compiled code runs.
+///
+/// Compiled code writes to the tape through `cells` for the whole run, so callbacks only ever
+/// touch the runner's input and output, and go through `cells` for the tape too. Taking the tape
+/// from the runner would invalidate the pointer compiled code is still using.
+struct Context<const N: usize, T: CellValue, I, O> {
+    runner: *mut Runner<N, T, I, O>,
+    cells: *mut T,
+    error: Option<ErrorKind>,
+}
+
+/// Reads into the cell at an index, returning nonzero if reading failed.
+extern "C" fn read<const N: usize, T: CellValue, I: Read, O: Write>(
+    context: *mut c_void,
+    index: usize,
+) -> u32 {
+    // SAFETY: Compiled code passes back the context given to it by `Jit::run`, whose runner is
+    // borrowed for the whole run, and always passes an index on the tape.
+    let context = unsafe { &mut *context.cast::<Context<N, T, I, O>>() };
+    let runner = unsafe { &mut *context.runner };
+    let cell = unsafe { &mut *context.cells.add(index) };
+
+    match runner.next_byte() {
+        Ok(value) => {
+            *cell = runner.options().eof.apply(value, *cell);
+            0
+        }
+        Err(error) => {
+            context.error = Some(error.kind());
+            1
+        }
+    }
+}
+
+/// Writes the cell at an index, returning nonzero if writing failed.
+extern "C" fn write<const N: usize, T: CellValue, I: Read, O: Write>(
+    context: *mut c_void,
+    index: usize,
+) -> u32 {
+    // SAFETY: As in `read`.
+    let context = unsafe { &mut *context.cast::<Context<N, T, I, O>>() };
+    let runner = unsafe { &mut *context.runner };
+    let value = unsafe { *context.cells.add(index) };
+
+    match runner.write_byte(value.to_u8()) {
+        Ok(()) => 0,
+        Err(error) => {
+            context.error = Some(error.kind());
+            1
+        }
+    }
+}
+
+/// A program compiled into machine code for a tape of `N` cells of type `T`.
+///
+/// Compiled code always wraps the pointer around the ends of the tape and never checks loop
+/// balance. Runners with any other options, and programs which use dumps or procedures, are run by
+/// the bytecode interpreter instead, so every runner behaves the same whichever engine is used.
+pub struct Jit<const N: usize, T: CellValue = u8> {
+    /// The compiled code, or `None` if the program can only be interpreted.
+    memory: Option<ExecutableMemory>,
+    bytecode: Bytecode,
+    cell: PhantomData<T>,
+}
+
+impl<const N: usize, T: CellValue> Jit<N, T> {
+    /// Compiles a program into machine code, returning an error if executable memory couldn't be
+    /// allocated.
+    pub fn new(program: &Program) -> io::Result<Self> {
+        let bytecode = program.bytecode();
+
+        let memory = if bytecode.extended() {
+            None
+        } else {
+            let code = Assembler::compile(&bytecode, N, size_of::<T>(), &Callbacks { len: N })
+                .expect("bytecode without procedures always compiles");
+            Some(ExecutableMemory::new(&code)?)
+        };
+
+        Ok(Self {
+            memory,
+            bytecode,
+            cell: PhantomData,
+        })
+    }
+
+    /// Runs the compiled program on a runner until it completes, returning why it stopped.
+    pub fn run<I: Read, O: Write>(&self, runner: &mut Runner<N, T, I, O>) -> Status {
+        let options = runner.options();
+
+        let memory = match &self.memory {
+            Some(memory)
+                if options.tape == TapePolicy::Wrap
+                    && options.overflow == Overflow::Wrap
+                    && !options.check_balance =>
+            {
+                memory
+            }
+            _ => return self.bytecode.run_limited(runner, Limit::default()).0,
+        };
+
+        let (cells, index) = runner.tape_mut();
+        let (cells, index) = (cells.as_mut_ptr(), *index);
+
+        let mut context = Context {
+            runner: ptr::addr_of_mut!(*runner),
+            cells,
+            error: None,
+        };
+
+        // SAFETY: The code was compiled for a tape of `N` cells, which a wrapping tape always has,
+        // and the callbacks were instantiated for the same types as the context.
+        let index = unsafe {
+            let entry = std::mem::transmute::<*mut c_void, Entry>(memory.address);
+
+            entry(
+                cells.cast(),
+                ptr::addr_of_mut!(context).cast(),
+                index,
+                read::<N, T, I, O> as *const () as usize,
+                write::<N, T, I, O> as *const () as usize,
+            )
+        };
+
+        let error = context.error;
+        *runner.tape_mut().1 = index;
+
+        match (error, runner.flush()) {
+            (Some(kind), _) => Status::Io(kind),
+            (None, Err(error)) => Status::Io(error.kind()),
+            (None, Ok(())) => Status::Halted,
+        }
+    }
+}
+
+/// Runs compiled code as a function which calls back into a runner for input and output.
+struct Callbacks {
+    len: usize,
+}
+
+impl Callbacks {
+    /// Emits a call to the callback in `r15` or `rbp` with the context and the index of the
+    /// current cell, leaving the program if it fails.
+    fn call(assembler: &mut Assembler, call: &[u8]) {
+        // mov rdi, r14; mov rsi, r12
+        assembler.emit(&[0x4C, 0x89, 0xF7, 0x4C, 0x89, 0xE6]);
+        assembler.emit(call);
+
+        // test eax, eax; jnz exit
+        assembler.emit(&[0x85, 0xC0]);
+        assembler.exit(&[0x0F, 0x85]);
+    }
+}
+
+impl Environment for Callbacks {
+    fn prologue(&self, assembler: &mut Assembler) {
+        // push rbx; push rbp; push r12; push r13; push r14; push r15; sub rsp, 8
+        assembler.emit(&[0x53, 0x55, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57]);
+        assembler.emit(&[0x48, 0x83, 0xEC, 0x08]);
+
+        // mov rbx, rdi; mov r14, rsi; mov r12, rdx; mov r15, rcx; mov rbp, r8
+        assembler.emit(&[0x48, 0x89, 0xFB, 0x49, 0x89, 0xF6, 0x49, 0x89, 0xD4]);
+        assembler.emit(&[0x49, 0x89, 0xCF, 0x4C, 0x89, 0xC5]);
+
+        // mov r13, len
+        assembler.emit(&[0x49, 0xBD]);
+        assembler.emit(&(self.len as u64).to_le_bytes());
+    }
+
+    fn read(&self, assembler: &mut Assembler) {
+        // call r15
+        Self::call(assembler, &[0x41, 0xFF, 0xD7]);
+    }
+
+    fn write(&self, assembler: &mut Assembler) {
+        // call rbp
+        Self::call(assembler, &[0xFF, 0xD5]);
+    }
+
+    fn epilogue(&self, assembler: &mut Assembler) {
+        // mov rax, r12
+        assembler.emit(&[0x4C, 0x89, 0xE0]);
+
+        // add rsp, 8; pop r15; pop r14; pop r13; pop r12; pop rbp; pop rbx; ret
+        assembler.emit(&[0x48, 0x83, 0xC4, 0x08]);
+        assembler.emit(&[
+            0x41, 0x5F, 0x41, 0x5E, 0x41, 0x5D, 0x41, 0x5C, 0x5D, 0x5B, 0xC3,
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compiler::backend::{
+            tests::{Case, CORPUS, TAPE_SIZE},
+            CellWidth,
+        },
+        runner::RunnerOptions,
+    };
+
+    #[test]
+    fn compiled_programs_match_the_interpreter() {
+        fn run<T: CellValue>(case: &Case) -> Vec<u8> {
+            let jit = Jit::<TAPE_SIZE, T>::new(&case.program()).unwrap();
+            assert!(jit.memory.is_some(), "{} wasn't compiled", case.name);
+
+            let mut runner = Runner::with_options(case.input, case.runner_options());
+            assert_eq!(jit.run(&mut runner), Status::Halted, "{}", case.name);
+            runner.output().clone()
+        }
+
+        for case in CORPUS {
+            let output = match case.cell {
+                CellWidth::U8 => run::<u8>(case),
+                CellWidth::U16 => run::<u16>(case),
+                CellWidth::U32 => run::<u32>(case),
+            };
+
+            assert_eq!(output, case.expected(), "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn io_errors_stop_the_program() {
+        struct Broken;
+
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let jit = Jit::<TAPE_SIZE>::new(&Program::new("+.+.").unwrap()).unwrap();
+        let mut runner = Runner::with_io(io::empty(), Broken, RunnerOptions::default());
+
+        assert_eq!(jit.run(&mut runner), Status::Io(ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn overflow_checks_run_on_the_interpreter() {
+        let options = RunnerOptions {
+            overflow: Overflow::Error,
+            ..RunnerOptions::default()
+        };
+
+        let jit = Jit::<TAPE_SIZE>::new(&Program::new("+>-").unwrap()).unwrap();
+        let mut runner = Runner::with_options(b"", options);
+
+        assert!(matches!(
+            jit.run(&mut runner),
+            Status::Overflow { index: 1, .. }
+        ));
+    }
+}
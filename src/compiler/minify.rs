@@ -0,0 +1,190 @@
+//! Minifies programs by removing everything which can't affect what they do.
+
+use super::{error::ParseError, push, Instruction, Program};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Source which has been minified, along with how much smaller it got.
+pub struct Minified {
+    /// The minified source.
+    pub source: String,
+
+    /// The length of the original source in bytes.
+    pub before: usize,
+
+    /// The length of the minified source in bytes.
+    pub after: usize,
+}
+
+/// Minifies source, returning an error if it is malformed.
+///
+/// Characters which aren't commands are removed, adjacent inverse commands such as `+-` and `><`
+/// cancel out, and dead loops are deleted as described in `Program::minify`.
+pub fn minify(source: &str) -> Result<Minified, ParseError> {
+    let minified = Program::new(source)?.minify().to_string();
+
+    Ok(Minified {
+        before: source.len(),
+        after: minified.len(),
+        source: minified,
+    })
+}
+
+impl Program {
+    /// Returns a copy of this program without its dead loops, which are loops that can never run
+    /// because the current cell is always zero when they are reached. That is the case for loops
+    /// right after another loop, which only ever exits on a zero cell, and for loops before the
+    /// program writes to any cell. Commands on either side of a deleted loop are folded together.
+    pub fn minify(&self) -> Program {
+        // Each entry holds the remaining instructions of a list, the minified ones, the span of the
+        // node the list is the body of along with whether the tape was pristine before it if it is
+        // a procedure, and whether the current cell is known to be zero.
+        let mut stack = vec![(self.instructions.iter(), Vec::new(), None, true)];
+
+        // Whether no cell has been changed yet, in which case every cell is zero.
+        let mut pristine = true;
+
+        loop {
+            let (remaining, minified, _, zero) =
+                stack.last_mut().expect("the stack is never empty here");
+
+            let Some(node) = remaining.next() else {
+                let (_, body, parent, _) = stack.pop().expect("the stack is never empty here");
+
+                match (stack.last_mut(), parent) {
+                    (Some((_, parent, _, zero)), Some((span, None))) => {
+                        push(parent, Instruction::Loop(body), span);
+                        *zero = true;
+                    }
+
+                    // Defining a procedure doesn't run it, so it can't have changed any cells yet.
+                    (Some((_, parent, _, _)), Some((span, Some(was_pristine)))) => {
+                        push(parent, Instruction::Procedure(body), span);
+                        pristine = was_pristine;
+                    }
+
+                    _ => {
+                        return Program {
+                            instructions: body,
+                            input: self.input.clone(),
+                        }
+                    }
+                }
+
+                continue;
+            };
+
+            match &node.instruction {
+                Instruction::Loop(_)
+                | Instruction::Clear
+                | Instruction::MulAdd(_)
+                | Instruction::Scan(_)
+                    if *zero => {}
+
+                Instruction::Loop(body) => {
+                    pristine = false;
+                    stack.push((body.iter(), Vec::new(), Some((node.span, None)), false));
+                }
+
+                // Procedures can be called from anywhere, so nothing is known about the tape when
+                // their bodies start.
+                Instruction::Procedure(body) => {
+                    stack.push((
+                        body.iter(),
+                        Vec::new(),
+                        Some((node.span, Some(pristine))),
+                        false,
+                    ));
+
+                    pristine = false;
+                }
+
+                Instruction::Call => {
+                    minified.push(node.clone());
+                    pristine = false;
+                    *zero = false;
+                }
+
+                Instruction::Clear | Instruction::MulAdd(_) | Instruction::Scan(_) => {
+                    minified.push(node.clone());
+                    pristine = false;
+                    *zero = true;
+                }
+
+                Instruction::Add(_) | Instruction::Read => {
+                    push(minified, node.instruction.clone(), node.span);
+                    pristine = false;
+                    *zero = false;
+                }
+
+                Instruction::Move(_) => {
+                    push(minified, node.instruction.clone(), node.span);
+                    *zero = pristine;
+                }
+
+                Instruction::Write | Instruction::Dump => minified.push(node.clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::minify;
+    use crate::compiler::{ParseOptions, Program};
+
+    const HELLO_WORLD: &str = "[ prints hello world ] ++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+    #[test]
+    fn comments_and_inverse_commands_are_removed() {
+        let minified = minify("a + b - c + > d < .").unwrap();
+
+        assert_eq!(minified.source, "+.");
+        assert_eq!(minified.before, 19);
+        assert_eq!(minified.after, 2);
+    }
+
+    #[test]
+    fn dead_loops_are_removed() {
+        for (source, expected) in [
+            ("[comment loop]+.", "+."),
+            (">>[-]<[>+<-]+.", ">+."),
+            ("+[-][+.][>]", "+[-]"),
+            (",[->+<]>[-][.]", ",[->+<]>[-]"),
+            ("+[[-][.]]", "+[[-]]"),
+            ("+>[-]<.", "+>[-]<."),
+        ] {
+            assert_eq!(minify(source).unwrap().source, expected, "{source:?}");
+        }
+    }
+
+    #[test]
+    fn procedure_bodies_keep_their_loops() {
+        let options = ParseOptions {
+            procedures: true,
+            ..ParseOptions::default()
+        };
+
+        let program = Program::with_options("([.])[.]:", options).unwrap();
+        assert_eq!(program.minify().to_string(), "([.]):");
+    }
+
+    #[test]
+    fn minified_programs_behave_the_same() {
+        for source in [
+            HELLO_WORLD,
+            "[loop] ,[.,] echoes its input [until] the end",
+            "++[>+++<-]>[<++>-]<[-][never runs].",
+            ">>+<<[-]>>[<<+>>-][-]<<.",
+        ] {
+            let minified = minify(source).unwrap();
+            assert!(minified.after < minified.before, "{source:?}");
+
+            let expected = Program::new(source).unwrap().run::<64, u8>(b"input");
+            let actual = Program::new(&minified.source)
+                .unwrap()
+                .run::<64, u8>(b"input");
+
+            assert_eq!(actual.output(), expected.output(), "{source:?}");
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! A compiler for Brainf*** programs.
+
+pub mod analysis;
+pub mod ast;
+pub mod backend;
+pub mod bytecode;
+pub mod debugger;
+pub mod dialect;
+pub mod encoding;
+pub mod error;
+pub mod execution;
+pub mod format;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub mod jit;
+pub mod minify;
+pub mod optimizer;
+pub mod profiler;
+mod recorder;
+
+use self::{
+    ast::{Instruction, Node},
+    bytecode::Bytecode,
+    error::{Location, ParseError, Span},
+    execution::{Limit, Outcome, Status},
+    optimizer::optimize,
+};
+use crate::runner::{cell::CellValue, Runner, RunnerOptions};
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+/// Pushes an instruction onto a list, folding it into the previous instruction where possible.
+/// Instructions which fold into a no-op are removed entirely, which allows the instruction before
+/// them to be folded into by later instructions.
+fn push(list: &mut Vec<Node>, instruction: Instruction, span: Span) {
+    let Some(previous) = list.last_mut() else {
+        list.push(Node { instruction, span });
+        return;
+    };
+
+    let cancelled = match (&mut previous.instruction, &instruction) {
+        (Instruction::Add(previous), Instruction::Add(value)) => {
+            *previous = previous.wrapping_add(*value);
+            *previous == 0
+        }
+
+        (Instruction::Move(previous), Instruction::Move(offset)) => {
+            *previous += offset;
+            *previous == 0
+        }
+
+        _ => {
+            list.push(Node { instruction, span });
+            return;
+        }
+    };
+
+    previous.span.end = span.end;
+
+    if cancelled {
+        list.pop();
+    }
+}
+
+/// Gets every command in source, including the given extension commands, along with its span.
+/// Every other character is a comment.
+fn commands<'a>(source: &'a str, extensions: &'a str) -> impl Iterator<Item = (char, Span)> + 'a {
+    let mut line = 1;
+    let mut column = 0;
+
+    source.char_indices().filter_map(move |(offset, char)| {
+        column += 1;
+
+        let location = Location {
+            offset,
+            line,
+            column,
+        };
+
+        if char == '\n' {
+            line += 1;
+            column = 0;
+        }
+
+        // Every command is a single byte, so the next character starts right after it.
+        ("+-<>,.[]".contains(char) || extensions.contains(char)).then_some((
+            char,
+            Span {
+                start: location,
+                end: Location {
+                    offset: offset + 1,
+                    line: location.line,
+                    column: location.column + 1,
+                },
+            },
+        ))
+    })
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, ParseError> {
+    parse_commands(commands(source, ""))
+}
+
+/// Parses a sequence of commands, written as the Brainf*** characters they stand for, along with
+/// the span of source each one came from. This includes the commands of extended Brainf***, so
+/// callers decide which of them are commands rather than comments.
+fn parse_commands(
+    commands: impl IntoIterator<Item = (char, Span)>,
+) -> Result<Vec<Node>, ParseError> {
+    // Each entry holds the location and bracket of an unclosed loop or procedure, along with the
+    // list it is in.
+    let mut all_lists: Vec<(Location, char, Vec<Node>)> = Vec::new();
+    let mut current_list: Vec<Node> = Vec::new();
+
+    for (char, span) in commands {
+        match char {
+            '+' => push(&mut current_list, Instruction::Add(1), span),
+            '-' => push(&mut current_list, Instruction::Add(-1), span),
+            '<' => push(&mut current_list, Instruction::Move(-1), span),
+            '>' => push(&mut current_list, Instruction::Move(1), span),
+            ',' => push(&mut current_list, Instruction::Read, span),
+            '.' => push(&mut current_list, Instruction::Write, span),
+            '#' => push(&mut current_list, Instruction::Dump, span),
+            ':' => push(&mut current_list, Instruction::Call, span),
+
+            '[' | '(' => {
+                let sub_instruction_list: Vec<Node> = Vec::new();
+                all_lists.push((span.start, char, current_list));
+                current_list = sub_instruction_list;
+            }
+
+            ']' | ')' => {
+                let sub_instruction_list = current_list;
+
+                let opening = if char == ']' { '[' } else { '(' };
+
+                let Some((start, _, mut last_instruction_list)) =
+                    all_lists.pop_if(|(_, bracket, _)| *bracket == opening)
+                else {
+                    return Err(ParseError::UnmatchedClosingBracket(span.start));
+                };
+
+                let instruction = if char == ']' {
+                    Instruction::Loop(sub_instruction_list)
+                } else {
+                    Instruction::Procedure(sub_instruction_list)
+                };
+
+                push(
+                    &mut last_instruction_list,
+                    instruction,
+                    Span {
+                        start,
+                        end: span.end,
+                    },
+                );
+                current_list = last_instruction_list;
+            }
+
+            _ => {}
+        };
+    }
+
+    if !all_lists.is_empty() {
+        Err(ParseError::UnmatchedOpeningBracket(
+            all_lists
+                .into_iter()
+                .map(|(location, _, _)| location)
+                .collect(),
+        ))
+    } else {
+        Ok(current_list)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// Options which enable the commands of extended Brainf***. Without them, the characters of these
+/// commands are comments like any other.
+pub struct ParseOptions {
+    /// Whether `#` records a snapshot of the tape, which can be read back with `Runner::dumps`.
+    pub dump: bool,
+
+    /// Whether the first `!` ends the program, with everything after it being input which is read
+    /// before any other input.
+    pub embedded_input: bool,
+
+    /// Whether to parse pbrain procedures. `(` and `)` surround the body of a procedure, which is
+    /// identified by the value of the current cell when it is reached, and `:` calls the procedure
+    /// identified by the value of the current cell.
+    pub procedures: bool,
+}
+
+#[derive(Clone, Debug)]
+/// A parsed program.
+pub struct Program {
+    instructions: Vec<Node>,
+
+    /// The input embedded in the program's source, which is read before any other input.
+    input: Vec<u8>,
+}
+
+impl Program {
+    /// Attempts to parse a program source, returning an error if it is malformed.
+    pub fn new(source: &str) -> Result<Self, ParseError> {
+        Self::with_options(source, ParseOptions::default())
+    }
+
+    /// Attempts to parse a program source with the given extensions enabled, returning an error if
+    /// it is malformed.
+    pub fn with_options(source: &str, options: ParseOptions) -> Result<Self, ParseError> {
+        let (source, input) = match source.split_once('!') {
+            Some((source, input)) if options.embedded_input => (source, input.as_bytes()),
+            _ => (source, &[][..]),
+        };
+
+        let mut extensions = String::new();
+
+        if options.dump {
+            extensions += "#";
+        }
+
+        if options.procedures {
+            extensions += "():";
+        }
+
+        Ok(Program {
+            instructions: optimize(parse_commands(commands(source, &extensions))?),
+            input: input.to_vec(),
+        })
+    }
+
+    /// Gets the input embedded in the program's source, which is read before any other input.
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    /// Compiles this program into flat bytecode. Compile once and use `Bytecode::run` directly
+    /// when running the same program many times.
+    pub fn bytecode(&self) -> Bytecode {
+        Bytecode::new(self)
+    }
+
+    /// Runs the program on a given input, outputting a `Runner` once complete. The runner's cells
+    /// can be any `CellValue`, such as `u16` for 16-bit cells.
+    pub fn run<const N: usize, T: CellValue>(&self, input: &[u8]) -> Runner<N, T> {
+        self.run_with_options(input, RunnerOptions::default())
+            .runner
+    }
+
+    /// Runs the program on a given input using custom runner options until it completes,
+    /// returning why it stopped along with the runner. The runner's cells can be any `CellValue`,
+    /// such as `u16` for 16-bit cells.
+    ///
+    /// The program stops early if the pointer moves past the end of a tape using
+    /// `TapePolicy::Error`, in which case the status says where.
+    pub fn run_with_options<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+        options: RunnerOptions,
+    ) -> Outcome<N, T> {
+        self.run_limited(input, options, Limit::default())
+    }
+
+    /// Compiles the program into machine code and runs it on a given input until it completes,
+    /// returning why it stopped along with the runner. This gives the same result as `run` but is
+    /// much faster for long-running programs. Compile once with `Jit::new` when running the same
+    /// program many times.
+    #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+    pub fn run_jit<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+    ) -> io::Result<(Status, Runner<N, T>)> {
+        let mut runner = Runner::new(input);
+        runner.queue_input(&self.input);
+
+        let status = jit::Jit::new(self)?.run(&mut runner);
+
+        Ok((status, runner))
+    }
+
+    /// Runs the program on a given input until it completes or takes `fuel` steps, as counted by
+    /// `Limit::fuel`.
+    pub fn run_with_fuel<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+        fuel: u64,
+    ) -> Outcome<N, T> {
+        self.run_limited(input, RunnerOptions::default(), Limit::fuel(fuel))
+    }
+
+    /// Runs the program on a given input until it completes or `timeout` has elapsed.
+    pub fn run_with_timeout<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+        timeout: Duration,
+    ) -> Outcome<N, T> {
+        self.run_limited(
+            input,
+            RunnerOptions::default(),
+            Limit::deadline(Instant::now() + timeout),
+        )
+    }
+
+    /// Runs the program on a given input using custom runner options until it completes or reaches
+    /// a limit. The runner's cells can be any `CellValue`, such as `u16` for 16-bit cells.
+    pub fn run_limited<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+        options: RunnerOptions,
+        limit: Limit,
+    ) -> Outcome<N, T> {
+        self.run_streaming(input.iter().copied().collect(), Vec::new(), options, limit)
+    }
+
+    /// Runs the program against arbitrary input and output streams until it completes or reaches a
+    /// limit. Input is only read when the program asks for it, and output is flushed before each
+    /// read from the input stream and once the program stops, so this works for interactive
+    /// programs. Wrap unbuffered output, such as a `File`, in a `BufWriter` to avoid a system call
+    /// for every byte written.
+    pub fn run_streaming<const N: usize, T: CellValue, I: Read, O: Write>(
+        &self,
+        input: I,
+        output: O,
+        options: RunnerOptions,
+        limit: Limit,
+    ) -> Outcome<N, T, I, O> {
+        let mut runner = Runner::with_io(input, output, options);
+        runner.queue_input(&self.input);
+
+        let (status, steps) = self.bytecode().run_limited(&mut runner, limit);
+
+        Outcome {
+            status,
+            steps,
+            runner,
+        }
+    }
+
+    /// Runs the program interactively against stdin and stdout, returning why it stopped. Output
+    /// is flushed after every write whatever the `flush_writes` option is, so it shows up as soon
+    /// as the program produces it, even in the middle of a line.
+    pub fn run_stdio<const N: usize>(&self, options: RunnerOptions) -> Status {
+        let options = RunnerOptions {
+            flush_writes: true,
+            ..options
+        };
+
+        self.run_streaming::<N, u8, _, _>(
+            io::stdin().lock(),
+            io::stdout().lock(),
+            options,
+            Limit::default(),
+        )
+        .status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmatched_closing_brackets_report_where() {
+        let error = Program::new("+[-]\n>]<").unwrap_err();
+        let location = Location {
+            offset: 6,
+            line: 2,
+            column: 2,
+        };
+
+        assert_eq!(error, ParseError::UnmatchedClosingBracket(location));
+        assert_eq!(error.to_string(), "unmatched closing bracket at 2:2");
+    }
+
+    #[test]
+    fn unmatched_opening_brackets_report_where_from_the_outermost() {
+        // Columns count characters, while offsets count bytes.
+        let error = Program::new("[\n é[[-]").unwrap_err();
+        let outer = Location {
+            offset: 0,
+            line: 1,
+            column: 1,
+        };
+
+        let inner = Location {
+            offset: 5,
+            line: 2,
+            column: 3,
+        };
+
+        assert_eq!(
+            error,
+            ParseError::UnmatchedOpeningBracket(vec![outer, inner])
+        );
+        assert_eq!(error.location(), outer);
+        assert_eq!(error.to_string(), "unmatched opening brackets at 1:1, 2:3");
+    }
+
+    /// A stream which records how much had been written each time it was flushed.
+    #[derive(Default)]
+    struct Recorder {
+        written: Vec<u8>,
+        flushes: Vec<usize>,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buffer);
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streams_are_read_on_demand_and_flushed_before_reading() {
+        let program = Program::new(">++++++[<++++++++>-]<.,[.,]").unwrap();
+        let outcome = program.run_streaming::<4, u8, _, _>(
+            &b"abc"[..],
+            Recorder::default(),
+            RunnerOptions::default(),
+            Limit::default(),
+        );
+
+        // Output is flushed before every read of the input stream, and once the program stops.
+        assert!(outcome.halted());
+        assert_eq!(outcome.runner.output().written, b"0abc");
+        assert_eq!(outcome.runner.output().flushes, [1, 2, 3, 4, 4]);
+        assert!(outcome.runner.input().is_empty());
+    }
+
+    #[test]
+    fn streams_can_be_flushed_after_every_write() {
+        let program = Program::new("++++++[>++++++++<-]>.+.+.").unwrap();
+        let options = RunnerOptions {
+            flush_writes: true,
+            ..RunnerOptions::default()
+        };
+
+        let outcome = program.run_streaming::<4, u8, _, _>(
+            io::empty(),
+            Recorder::default(),
+            options,
+            Limit::default(),
+        );
+
+        assert_eq!(outcome.runner.output().written, b"012");
+        assert_eq!(outcome.runner.output().flushes, [1, 2, 3, 3]);
+    }
+}
@@ -0,0 +1,115 @@
+//! An optimization pass which replaces common loop shapes with single instructions.
+//!
+//! Most of the code emitted by the builders consists of clear loops (`[-]`), multiply-add loops
+//! (`[->+<]`, `[->+>+<<]`, and so on), and scan loops (`[>]`, `[<]`). Recognizing these lets the
+//! runner execute each of them in one step instead of one decrement at a time.
+
+use super::{
+    ast::{fold, Fold},
+    error::Span,
+    Instruction, Node,
+};
+
+/// Optimizes a list of instructions, including the bodies of loops and procedures.
+pub(super) fn optimize(instructions: Vec<Node>) -> Vec<Node> {
+    /// Replaces each loop once its body has been optimized.
+    struct Optimizer;
+
+    impl Fold for Optimizer {
+        fn fold(&mut self, node: Node, output: &mut Vec<Node>) {
+            match node {
+                Node {
+                    instruction: Instruction::Loop(body),
+                    span,
+                } => output.push(optimize_loop(body, span)),
+
+                node => output.push(node),
+            }
+        }
+    }
+
+    fold(instructions, &mut Optimizer)
+}
+
+/// Attempts to replace a loop with a single instruction, returning the loop unchanged if its body
+/// doesn't match any known idiom.
+fn optimize_loop(body: Vec<Node>, span: Span) -> Node {
+    let instruction = match body.as_slice() {
+        // `[-]` and `[+]`, or any odd step, always reach zero.
+        [Node {
+            instruction: Instruction::Add(value),
+            ..
+        }] if value % 2 != 0 => Instruction::Clear,
+
+        [Node {
+            instruction: Instruction::Move(step),
+            ..
+        }] => Instruction::Scan(*step),
+
+        _ => match multiply_targets(&body) {
+            Some(targets) if targets.is_empty() => Instruction::Clear,
+            Some(targets) => Instruction::MulAdd(targets),
+            None => Instruction::Loop(body),
+        },
+    };
+
+    Node { instruction, span }
+}
+
+/// Checks if a loop body only consists of `Add` and `Move` instructions, returns the pointer to
+/// where it started, and decrements or increments the starting cell by exactly one. If it does, the
+/// loop runs a fixed number of times based on the starting cell, so its effect on every other cell
+/// is a multiplication.
+///
+/// Returns a list of offsets and factors such that running the loop is equivalent to adding the
+/// starting cell's value times the factor to each offset, then clearing the starting cell.
+fn multiply_targets(body: &[Node]) -> Option<Vec<(isize, i32)>> {
+    let mut offset = 0;
+    let mut changes: Vec<(isize, i32)> = Vec::new();
+
+    for node in body {
+        match &node.instruction {
+            Instruction::Add(value) => {
+                match changes.iter_mut().find(|(target, _)| *target == offset) {
+                    Some((_, change)) => *change = change.wrapping_add(*value),
+                    None => changes.push((offset, *value)),
+                }
+            }
+
+            Instruction::Move(step) => offset += step,
+
+            _ => return None,
+        }
+    }
+
+    if offset != 0 {
+        return None;
+    }
+
+    let step = changes
+        .iter()
+        .find(|(target, _)| *target == 0)
+        .map(|(_, change)| *change)?;
+
+    // A loop that decrements its cell runs `value` times. A loop that increments it runs `-value`
+    // times, which is the same as negating every factor.
+    let negate = match step {
+        -1 => false,
+        1 => true,
+        _ => return None,
+    };
+
+    Some(
+        changes
+            .into_iter()
+            .filter(|(target, change)| *target != 0 && *change != 0)
+            .map(|(target, change)| {
+                if negate {
+                    (target, change.wrapping_neg())
+                } else {
+                    (target, change)
+                }
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,322 @@
+//! A profiler which counts how often each part of a program runs.
+
+use super::{
+    bytecode::{Bytecode, Op},
+    error::Location,
+    execution::{Limit, Outcome},
+    Program,
+};
+use crate::runner::{cell::CellValue, Runner, RunnerOptions};
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// How a single loop behaved over a profiled run.
+pub struct LoopProfile {
+    /// The location of the loop's `[`.
+    pub location: Location,
+
+    /// The number of times the loop was reached, including the times it was skipped.
+    pub entries: u64,
+
+    /// The total number of iterations the loop ran.
+    pub iterations: u64,
+
+    /// The number of operations executed by the loop, including its nested loops. Procedures
+    /// defined inside the loop only count the step which defines them, and the bodies of
+    /// procedures called from the loop aren't counted, since a procedure's operations are counted
+    /// together no matter where it's called from.
+    pub steps: u64,
+}
+
+#[derive(Clone, Debug)]
+/// The result of running a program while counting how many times each operation ran.
+///
+/// Loops which were replaced by a single operation, such as `[->+<]`, count as one operation each
+/// time they run, so only loops which remain loops in the bytecode are profiled as loops.
+pub struct Profile<const N: usize, T: CellValue = u8> {
+    /// The outcome of the run.
+    pub outcome: Outcome<N, T>,
+
+    bytecode: Bytecode,
+
+    /// The number of times each operation ran, by address.
+    hits: Vec<u64>,
+}
+
+impl Program {
+    /// Runs the program on a given input until it completes, counting how many times each
+    /// operation runs.
+    pub fn profile<const N: usize, T: CellValue>(&self, input: &[u8]) -> Profile<N, T> {
+        self.profile_limited(input, RunnerOptions::default(), Limit::default())
+    }
+
+    /// Runs the program on a given input using custom runner options until it completes or
+    /// reaches a limit, counting how many times each operation runs.
+    pub fn profile_limited<const N: usize, T: CellValue>(
+        &self,
+        input: &[u8],
+        options: RunnerOptions,
+        limit: Limit,
+    ) -> Profile<N, T> {
+        let bytecode = self.bytecode();
+        let mut hits = vec![0; bytecode.ops().len()];
+        let mut runner = Runner::with_options(input, options);
+        runner.queue_input(&self.input);
+
+        let (status, steps) =
+            bytecode.run_observed(&mut runner, limit, |address| hits[address] += 1);
+
+        Profile {
+            outcome: Outcome {
+                status,
+                steps,
+                runner,
+            },
+            bytecode,
+            hits,
+        }
+    }
+}
+
+impl<const N: usize, T: CellValue> Profile<N, T> {
+    /// Gets the bytecode which was profiled.
+    pub fn bytecode(&self) -> &Bytecode {
+        &self.bytecode
+    }
+
+    /// Gets the number of times each operation ran, by address.
+    pub fn hits(&self) -> &[u64] {
+        &self.hits
+    }
+
+    /// Gets the number of times the operations starting at a source offset ran. The end of a loop
+    /// isn't counted, since its location is the start of the loop.
+    pub fn hits_at(&self, offset: usize) -> u64 {
+        self.starts()
+            .filter(|&address| self.bytecode.location(address).offset == offset)
+            .map(|address| self.hits[address])
+            .sum()
+    }
+
+    /// Gets a profile of every loop, in the order they appear in the source.
+    pub fn loops(&self) -> Vec<LoopProfile> {
+        self.bytecode
+            .ops()
+            .iter()
+            .enumerate()
+            .filter_map(|(address, op)| match op {
+                Op::JumpIfZero(end) => Some(LoopProfile {
+                    location: self.bytecode.location(address),
+                    entries: self.hits[address],
+                    iterations: self.hits[end - 1],
+                    steps: self.steps_between(address, *end),
+                }),
+
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Gets a profile of the loops which executed the most operations, with the hottest first.
+    pub fn hottest_loops(&self, count: usize) -> Vec<LoopProfile> {
+        let mut loops = self.loops();
+        loops.sort_by_key(|profile| std::cmp::Reverse(profile.steps));
+        loops.truncate(count);
+        loops
+    }
+
+    /// Annotates the source the program was parsed from, with a line for each operation showing
+    /// how many times it ran next to the commands it came from. Lines are indented by loop depth,
+    /// so source from a builder, which is a single line, is still readable.
+    ///
+    /// If the source isn't the one the program was parsed from, operations whose source can't be
+    /// found are shown as `?` instead.
+    pub fn annotate(&self, source: &str) -> String {
+        let commands = self.commands();
+        let mut offsets: Vec<usize> = self
+            .starts()
+            .map(|address| self.bytecode.location(address).offset)
+            .collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+
+        let mut output = String::new();
+        let mut depth = 0;
+
+        for address in self.starts() {
+            let start = self.bytecode.location(address).offset;
+            let end = offsets
+                .get(offsets.partition_point(|&offset| offset <= start))
+                .copied()
+                .unwrap_or(source.len());
+
+            let commands: String = match source.get(start..end) {
+                Some(source) => source
+                    .chars()
+                    .filter(|char| commands.contains(*char))
+                    .collect(),
+
+                None => "?".to_string(),
+            };
+
+            let indent = "  ".repeat(depth);
+            writeln!(output, "{:>12}  {indent}{commands}", self.hits[address]).unwrap();
+
+            depth = (depth + commands.matches(['[', '(']).count())
+                .saturating_sub(commands.matches([']', ')']).count());
+        }
+
+        output
+    }
+
+    /// Reports how the run went, followed by the hottest loops and the annotated source. Each loop's
+    /// share is the part of all operations executed which it executed.
+    pub fn report(&self, source: &str, count: usize) -> String {
+        let steps = self.outcome.steps;
+        let executed: u64 = self.hits.iter().sum();
+        let mut output = String::new();
+
+        writeln!(output, "{:?} after {steps} steps", self.outcome.status).unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "hottest loops:").unwrap();
+        writeln!(
+            output,
+            "{:>12}  {:>6}  {:>12}  {:>12}  location",
+            "steps", "share", "iterations", "entries"
+        )
+        .unwrap();
+
+        for profile in self.hottest_loops(count) {
+            let share = profile.steps as f64 * 100.0 / executed.max(1) as f64;
+
+            writeln!(
+                output,
+                "{:>12}  {share:>5.1}%  {:>12}  {:>12}  {}",
+                profile.steps, profile.iterations, profile.entries, profile.location
+            )
+            .unwrap();
+        }
+
+        writeln!(output).unwrap();
+        writeln!(output, "annotated source:").unwrap();
+        output += &self.annotate(source);
+
+        output
+    }
+
+    /// Gets the number of times the operations between two addresses ran, leaving out the bodies
+    /// of procedures defined between them.
+    fn steps_between(&self, start: usize, end: usize) -> u64 {
+        let mut steps = 0;
+        let mut address = start;
+
+        while address < end {
+            steps += self.hits[address];
+
+            address = match self.bytecode.ops()[address] {
+                Op::Define(after) => after,
+                _ => address + 1,
+            };
+        }
+
+        steps
+    }
+
+    /// Gets the characters which are commands in the program's source. The extended ones are only
+    /// commands if they were parsed as such, in which case every one of them is in the bytecode.
+    fn commands(&self) -> String {
+        let ops = self.bytecode.ops();
+        let mut commands = "+-<>,.[]".to_string();
+
+        if ops.contains(&Op::Dump) {
+            commands += "#";
+        }
+
+        if ops.iter().any(|op| matches!(op, Op::Define(_) | Op::Call)) {
+            commands += "():";
+        }
+
+        commands
+    }
+
+    /// Gets the addresses of every operation except the ends of loops and procedures, which share
+    /// their location with the start of the loop or procedure.
+    fn starts(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bytecode
+            .ops()
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| !matches!(op, Op::JumpIfNonzero(_) | Op::Return))
+            .map(|(address, _)| address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::ParseOptions;
+
+    #[test]
+    fn annotating_other_source_marks_what_is_missing() {
+        let program = Program::new("+++[>+++<-]>.").unwrap();
+        let annotated = program.profile::<16, u8>(&[]).annotate("+");
+
+        assert!(annotated.lines().count() > 1);
+        assert!(annotated.lines().skip(1).all(|line| line.ends_with('?')));
+    }
+
+    #[test]
+    fn annotations_only_show_enabled_commands() {
+        let source = "+(#:)[-]";
+        let annotated = Program::new(source)
+            .unwrap()
+            .profile::<16, u8>(&[])
+            .annotate(source);
+
+        assert!(!annotated.contains(['#', '(', ')', ':']));
+
+        let options = ParseOptions {
+            dump: true,
+            procedures: true,
+            ..ParseOptions::default()
+        };
+
+        let annotated = Program::with_options(source, options)
+            .unwrap()
+            .profile::<16, u8>(&[])
+            .annotate(source);
+
+        assert!(annotated.contains('#') && annotated.contains(':'));
+        assert!(annotated.contains('(') && annotated.contains(')'));
+    }
+
+    #[test]
+    fn shares_are_parts_of_the_operations_executed() {
+        // The scan runs once but takes a step for each cell it moves past, which isn't counted
+        // against the loop's share.
+        let program = Program::new("+>+>+>+<<<[>]<<<[.-]").unwrap();
+        let profile = program.profile::<16, u8>(&[]);
+        let executed: u64 = profile.hits().iter().sum();
+
+        assert!(profile.outcome.steps > executed);
+
+        let report = profile.report("", 1);
+        let share = profile.loops()[0].steps as f64 * 100.0 / executed as f64;
+        assert!(report.contains(&format!("{share:>5.1}%")));
+    }
+
+    #[test]
+    fn loops_leave_out_procedures_defined_inside_them() {
+        let options = ParseOptions {
+            procedures: true,
+            ..ParseOptions::default()
+        };
+
+        // The procedure is called after the loop, so only defining it counts towards the loop.
+        let program = Program::with_options("+[(>++++[-]<)-]+:", options).unwrap();
+        let profile = program.profile::<16, u8>(&[]);
+
+        assert!(profile.outcome.halted());
+        assert_eq!(profile.loops()[0].steps, 4);
+    }
+}
@@ -0,0 +1,200 @@
+//! Records how to undo each operation a program executes, so it can be run backwards.
+
+use super::{
+    bytecode::{Bytecode, Checkpoint, Execution, Op},
+    execution::{Limit, Status},
+};
+use crate::runner::{cell::CellValue, tape::TapePolicy, Runner};
+
+#[derive(Clone, Copy, Debug)]
+/// Everything needed to undo a single operation, other than the cells it wrote.
+struct Undo {
+    checkpoint: Checkpoint,
+
+    /// The index of the current cell before the operation.
+    index: usize,
+
+    /// The length and origin of the tape before the operation, which only change if it grew.
+    extent: (usize, usize),
+
+    /// The length of the write log before the operation.
+    writes: usize,
+
+    /// The input byte the operation read, if it read one, along with whether it was queued input.
+    read: Option<(u8, bool)>,
+
+    /// Whether the operation wrote a byte of output.
+    wrote: bool,
+
+    /// Whether the operation recorded a snapshot of the tape.
+    dumped: bool,
+}
+
+#[derive(Clone, Debug)]
+/// A log of how to undo each recorded operation.
+///
+/// Rather than snapshotting the tape, only the cells each operation writes are saved, so recording
+/// costs a few dozen bytes per operation no matter how large the tape is.
+pub(super) struct Recorder<T> {
+    undos: Vec<Undo>,
+
+    /// The index and previous value of every cell written by a recorded operation, in order.
+    writes: Vec<(usize, T)>,
+}
+
+impl<T: CellValue> Recorder<T> {
+    /// Constructs an empty recorder.
+    pub(super) fn new() -> Self {
+        Self {
+            undos: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    /// Gets the number of operations which can be undone.
+    pub(super) fn len(&self) -> usize {
+        self.undos.len()
+    }
+
+    /// Executes the next operation like `Execution::step_limited`, recording how to undo it.
+    pub(super) fn step<const N: usize>(
+        &mut self,
+        execution: &mut Execution,
+        bytecode: &Bytecode,
+        runner: &mut Runner<N, T>,
+        limit: Limit,
+        steps: &mut u64,
+    ) -> Result<(), Status> {
+        let op = &bytecode.ops()[execution.address];
+        let index = runner.index();
+        let cells = runner.cells();
+
+        let mut undo = Undo {
+            checkpoint: execution.checkpoint(bytecode, runner.get()),
+            index,
+            extent: runner.extent(),
+            writes: self.writes.len(),
+            read: None,
+            wrote: false,
+            dumped: false,
+        };
+
+        match op {
+            Op::Add(_) | Op::Clear => self.writes.push((index, cells[index])),
+
+            Op::Read => {
+                self.writes.push((index, cells[index]));
+                undo.read = match runner.queued_input().front() {
+                    Some(&byte) => Some((byte, true)),
+                    None => runner.input().front().map(|&byte| (byte, false)),
+                };
+            }
+
+            Op::MulAdd(targets) if cells[index] != T::ZERO => {
+                self.writes.push((index, cells[index]));
+
+                for (offset, _) in targets {
+                    let target = match runner.options().tape {
+                        TapePolicy::Wrap => (index as isize + offset).rem_euclid(cells.len() as _),
+                        _ => index as isize + offset,
+                    };
+
+                    // Targets off the end of the tape are either new cells, which are removed
+                    // when the tape shrinks back, or errors, which don't write anything.
+                    if let Some(value) = usize::try_from(target).ok().and_then(|t| cells.get(t)) {
+                        self.writes.push((target as usize, *value));
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        let result = execution.step_limited(bytecode, runner, limit, steps);
+
+        undo.wrote = *op == Op::Write && result.is_ok();
+        undo.dumped = *op == Op::Dump;
+        self.undos.push(undo);
+
+        result
+    }
+
+    /// Undoes the last recorded operation, returning whether there was one.
+    pub(super) fn undo<const N: usize>(
+        &mut self,
+        execution: &mut Execution,
+        runner: &mut Runner<N, T>,
+    ) -> bool {
+        let Some(undo) = self.undos.pop() else {
+            return false;
+        };
+
+        // The tape has to shrink first, since the saved indices are from before it grew.
+        let (len, origin) = undo.extent;
+        runner.shrink(len, origin);
+
+        let (cells, index) = runner.tape_mut();
+
+        // Going backwards means a cell written twice ends up with its oldest value.
+        for (cell, value) in self.writes.drain(undo.writes..).rev() {
+            cells[cell] = value;
+        }
+
+        *index = undo.index;
+
+        match undo.read {
+            Some((byte, true)) => runner.queued_mut().push_front(byte),
+            Some((byte, false)) => runner.input_mut().push_front(byte),
+            None => {}
+        }
+
+        if undo.wrote {
+            runner.output_mut().pop();
+        }
+
+        if undo.dumped {
+            runner.dumps_mut().pop();
+        }
+
+        execution.restore(undo.checkpoint);
+
+        true
+    }
+
+    /// Gets the number of operations to undo to get back to right before the last recorded write
+    /// to the cell at a position, or `None` if no recorded operation wrote to it.
+    pub(super) fn since_write(&self, position: isize) -> Option<usize> {
+        let mut end = self.writes.len();
+
+        for (count, undo) in self.undos.iter().rev().enumerate() {
+            let origin = undo.extent.1 as isize;
+
+            if self.writes[undo.writes..end]
+                .iter()
+                .any(|(index, _)| *index as isize - origin == position)
+            {
+                return Some(count + 1);
+            }
+
+            end = undo.writes;
+        }
+
+        None
+    }
+
+    /// Gets the number of operations to undo to take back a number of output bytes, or `None` if
+    /// fewer than that many were written by recorded operations.
+    pub(super) fn since_output(&self, bytes: usize) -> Option<usize> {
+        if bytes == 0 {
+            return Some(0);
+        }
+
+        self.undos
+            .iter()
+            .rev()
+            .enumerate()
+            .filter(|(_, undo)| undo.wrote)
+            .nth(bytes - 1)
+            .map(|(count, _)| count + 1)
+    }
+}
@@ -1,13 +1,13 @@
-#![doc = ""]
+//! An intermediary compiler for Brainf*** built on Rust's borrow checker and operator overloading.
 #![deny(missing_docs)]
 
-use crate::builder::allocator::core::AllocatingBuilder;
+use crate::{builder::allocator::core::AllocatingBuilder, compiler::error::ParseError};
 
 pub mod builder;
 pub mod compiler;
 pub mod runner;
 
-fn main() -> Result<(), &'static str> {
+fn main() -> Result<(), ParseError> {
     let builder = AllocatingBuilder::<65536>::new();
 
     let a = builder.u8(32);
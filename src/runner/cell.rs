@@ -0,0 +1,111 @@
+//! Defines the types which can be used as the cells of a runner's tape.
+
+use std::{
+    fmt::{Debug, UpperHex},
+    hash::Hash,
+};
+
+/// A type which can be stored in a runner's tape. Arithmetic wraps around at the width of the
+/// type by default, so the width of the cell type determines where programs overflow. The runner's
+/// `overflow` option can instead make overflowing an error.
+///
+/// Input and output are always bytes. Reading zero-extends a byte into a cell, and writing
+/// truncates a cell to its lowest byte.
+pub trait CellValue: Copy + Debug + Hash + Eq + UpperHex + 'static {
+    /// The value every cell starts out with.
+    const ZERO: Self;
+
+    /// The largest value a cell can hold, which is also -1 in two's complement.
+    const MAX: Self;
+
+    /// Converts a signed amount into this type, wrapping it to the width of this type.
+    fn from_i32(value: i32) -> Self;
+
+    /// Widens a byte read from input into this type.
+    fn from_u8(value: u8) -> Self;
+
+    /// Truncates this value to a byte to be written into output.
+    fn to_u8(self) -> u8;
+
+    /// Widens this value into an unsigned 32-bit number, such as to identify a procedure by it.
+    fn to_u32(self) -> u32;
+
+    /// Adds two values, wrapping on overflow.
+    fn wrapping_add(self, other: Self) -> Self;
+
+    /// Multiplies two values, wrapping on overflow.
+    fn wrapping_mul(self, other: Self) -> Self;
+
+    /// Adds a signed amount to this value, returning `None` if the result is negative or larger
+    /// than `MAX`.
+    fn checked_add_signed(self, value: i64) -> Option<Self>;
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// What happens when arithmetic takes a cell below zero or past its largest value.
+pub enum Overflow {
+    #[default]
+    /// The cell wraps around at the width of its type.
+    Wrap,
+
+    /// Overflowing is an error, which stops the program and leaves the cell unchanged. Runs of `+`
+    /// and `-` are folded together when parsing, so only the value each run ends up at has to be
+    /// in range, and `-+` on a zero cell never fails. Loops which the optimizer replaces count
+    /// their cell down to zero, so a clear never fails, and a multiply-add only fails if one of
+    /// its targets overflows, in which case no cell is changed.
+    ///
+    /// Only the interpreter checks for overflow. Backends always translate programs with wrapping
+    /// cells.
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// An error returned when arithmetic overflows a cell under `Overflow::Error`.
+pub struct CellOverflow {
+    /// The index of the cell which overflowed.
+    pub index: usize,
+}
+
+macro_rules! impl_cell_value {
+    ($($ty:ty),*) => {
+        $(
+            impl CellValue for $ty {
+                const ZERO: Self = 0;
+
+                const MAX: Self = <$ty>::MAX;
+
+                fn from_i32(value: i32) -> Self {
+                    value as $ty
+                }
+
+                fn from_u8(value: u8) -> Self {
+                    value as $ty
+                }
+
+                fn to_u8(self) -> u8 {
+                    self as u8
+                }
+
+                fn to_u32(self) -> u32 {
+                    self as u32
+                }
+
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$ty>::wrapping_add(self, other)
+                }
+
+                fn wrapping_mul(self, other: Self) -> Self {
+                    <$ty>::wrapping_mul(self, other)
+                }
+
+                fn checked_add_signed(self, value: i64) -> Option<Self> {
+                    i64::from(self)
+                        .checked_add(value)
+                        .and_then(|value| <$ty>::try_from(value).ok())
+                }
+            }
+        )*
+    };
+}
+
+impl_cell_value!(u8, u16, u32);
@@ -0,0 +1,608 @@
+//! A runner for Brainf*** programs.
+
+pub mod cell;
+pub mod tape;
+
+use self::{
+    cell::{CellOverflow, CellValue, Overflow},
+    tape::{OutOfBounds, Tape, TapePolicy},
+};
+use crate::compiler::{
+    error::Location,
+    execution::{Limit, Status},
+};
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Display},
+    io::{self, ErrorKind, Read, Write},
+};
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// What a read command does to the current cell once input is exhausted. Brainf*** programs in
+/// the wild are written against each of these conventions.
+pub enum Eof {
+    #[default]
+    /// Sets the cell to zero.
+    Zero,
+
+    /// Sets the cell to -1, which is its largest value, such as 255 for byte cells. With byte
+    /// cells, this can't be told apart from reading a `0xFF` byte.
+    MinusOne,
+
+    /// Leaves the cell unchanged.
+    Unchanged,
+}
+
+impl Eof {
+    /// Gets the value a read stores in a cell holding `current`, given the byte read if there was
+    /// one.
+    pub(crate) fn apply<T: CellValue>(self, value: Option<u8>, current: T) -> T {
+        match (value, self) {
+            (Some(value), _) => T::from_u8(value),
+            (None, Eof::Zero) => T::ZERO,
+            (None, Eof::MinusOne) => T::MAX,
+            (None, Eof::Unchanged) => current,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// Options which change how a `Runner` behaves.
+pub struct RunnerOptions {
+    /// Whether to record a diagnostic whenever a loop iteration ends with the pointer somewhere
+    /// other than where the iteration started. Standard Brainf*** allows loops to move the pointer,
+    /// so this is disabled by default.
+    pub check_balance: bool,
+
+    /// What reading does once input is exhausted.
+    pub eof: Eof,
+
+    /// What happens when the pointer moves past either end of the tape.
+    pub tape: TapePolicy,
+
+    /// What happens when arithmetic takes a cell below zero or past its largest value.
+    pub overflow: Overflow,
+
+    /// Whether to flush output after every write, so it shows up as soon as the program produces
+    /// it. Otherwise, output is only flushed before reading input and once the program stops.
+    pub flush_writes: bool,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// A diagnostic recorded when a loop iteration moves the pointer and balance checks are enabled.
+pub struct UnbalancedLoop {
+    /// The location of the loop's `[` in the source, if it is known.
+    pub location: Option<Location>,
+
+    /// The pointer position at the start of the iteration.
+    pub start: isize,
+
+    /// The pointer position at the end of the iteration.
+    pub end: isize,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// An error returned when a scan stops before it reaches a zero cell.
+pub enum ScanError {
+    /// The pointer moved past the end of a tape which doesn't allow it.
+    OutOfBounds(OutOfBounds),
+
+    /// The scan reached a limit, which is either `Status::OutOfFuel` or
+    /// `Status::DeadlineExceeded`.
+    Limit(Status),
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// An error returned when a multiply-add can't change every cell it targets.
+pub enum MulAddError {
+    /// A target is off the end of a tape which doesn't allow it.
+    OutOfBounds(OutOfBounds),
+
+    /// A target would overflow under `Overflow::Error`.
+    Overflow(CellOverflow),
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// A snapshot of the tape recorded by a dump command, which is the `#` of extended Brainf***.
+pub struct TapeDump<T> {
+    /// The location of the dump command in the source, if it is known.
+    pub location: Option<Location>,
+
+    /// The cells of the tape, up to the current cell or the last nonzero cell, whichever is
+    /// further along.
+    pub cells: Vec<T>,
+
+    /// The index of the current cell.
+    pub index: usize,
+}
+
+impl<T: CellValue> Display for TapeDump<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{location}: ")?;
+        }
+
+        write!(f, "{:?}", RunnerData(&self.cells, self.index))
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+/// A runner that only permits access to its internal data through standard Brainf*** commands.
+/// Cells are bytes by default, but any `CellValue` such as `u16` or `u32` can be used instead.
+///
+/// The tape starts out with `N` cells. Under a growing `TapePolicy`, `N` is only its initial size.
+///
+/// Input and output are buffered in memory by default, but any `Read` and `Write` implementations,
+/// such as stdin and stdout, can be used instead.
+pub struct Runner<const N: usize, T: CellValue = u8, I = VecDeque<u8>, O = Vec<u8>> {
+    tape: Tape<T>,
+    input: I,
+
+    /// Input which is read before anything left in `input`.
+    queued: VecDeque<u8>,
+
+    output: O,
+    options: RunnerOptions,
+    unbalanced_loops: Vec<UnbalancedLoop>,
+    dumps: Vec<TapeDump<T>>,
+}
+
+impl<const N: usize, T: CellValue> Runner<N, T> {
+    /// Constructs a new runner given input bytes.
+    pub fn new(input: &[u8]) -> Self {
+        Self::with_options(input, RunnerOptions::default())
+    }
+
+    /// Constructs a new runner given input bytes and options.
+    pub fn with_options(input: &[u8], options: RunnerOptions) -> Self {
+        Self::with_io(input.iter().copied().collect(), Vec::new(), options)
+    }
+}
+
+impl<const N: usize, T: CellValue, I, O> Runner<N, T, I, O> {
+    /// Constructs a new runner which reads from and writes to arbitrary streams.
+    pub fn with_io(input: I, output: O, options: RunnerOptions) -> Self {
+        if N == 0 {
+            panic!("cannot make a runner of size 0");
+        }
+
+        Runner {
+            tape: Tape::new(N, options.tape),
+            input,
+            queued: VecDeque::new(),
+            output,
+            options,
+            unbalanced_loops: Vec::new(),
+            dumps: Vec::new(),
+        }
+    }
+
+    /// Gets the input stream of this runner, which only contains input that hasn't been read yet.
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// Gets the input which will be read before anything left in the input stream.
+    pub fn queued_input(&self) -> &VecDeque<u8> {
+        &self.queued
+    }
+
+    /// Queues bytes to be read before anything left in the input stream, after any input queued
+    /// earlier. This is how input embedded in a program's source is read first.
+    pub fn queue_input(&mut self, bytes: &[u8]) {
+        self.queued.extend(bytes);
+    }
+
+    /// Gets the output stream of this runner.
+    pub fn output(&self) -> &O {
+        &self.output
+    }
+
+    /// Gets the options this runner was created with.
+    pub fn options(&self) -> RunnerOptions {
+        self.options
+    }
+
+    /// Gets the unbalanced loops recorded so far. Loops with a known location are only recorded the
+    /// first time they move the pointer. This is always empty unless `check_balance` is enabled.
+    pub fn unbalanced_loops(&self) -> &[UnbalancedLoop] {
+        &self.unbalanced_loops
+    }
+
+    /// Records that a loop moved the pointer.
+    pub fn report_unbalanced(&mut self, unbalanced: UnbalancedLoop) {
+        let already_reported = unbalanced.location.is_some()
+            && self
+                .unbalanced_loops
+                .iter()
+                .any(|previous| previous.location == unbalanced.location);
+
+        if !already_reported {
+            self.unbalanced_loops.push(unbalanced);
+        }
+    }
+
+    /// Gets the snapshots of the tape recorded by dump commands so far, in order.
+    pub fn dumps(&self) -> &[TapeDump<T>] {
+        &self.dumps
+    }
+
+    /// Records a snapshot of the tape, leaving out the zero cells at the end which the pointer
+    /// hasn't reached.
+    pub fn dump(&mut self, location: Option<Location>) {
+        let cells = &self.tape.cells;
+        let end = cells
+            .iter()
+            .rposition(|&cell| cell != T::ZERO)
+            .map_or(0, |last| last + 1)
+            .max(self.tape.index + 1);
+
+        self.dumps.push(TapeDump {
+            location,
+            cells: cells[..end].to_vec(),
+            index: self.tape.index,
+        });
+    }
+
+    /// Gets the value of the current cell.
+    pub fn get(&self) -> T {
+        self.tape.get()
+    }
+
+    /// Gets the index of the current cell.
+    pub fn index(&self) -> usize {
+        self.tape.index
+    }
+
+    /// Gets the position of the current cell relative to the first cell the tape started with.
+    /// This is the same as `index` unless the tape has grown to the left, in which case it can be
+    /// negative.
+    pub fn position(&self) -> isize {
+        self.tape.index as isize - self.tape.origin as isize
+    }
+
+    /// Increments the current cell.
+    pub fn inc(&mut self) -> Result<(), CellOverflow> {
+        self.add(1)
+    }
+
+    /// Decrements the current cell.
+    pub fn dec(&mut self) -> Result<(), CellOverflow> {
+        self.add(-1)
+    }
+
+    /// Adds a value to the current cell. What happens on overflow depends on the `overflow`
+    /// option.
+    pub fn add(&mut self, value: i32) -> Result<(), CellOverflow> {
+        let index = self.tape.index;
+        let overflow = self.options.overflow;
+        let cell = self.tape.current_mut();
+
+        *cell = match overflow {
+            Overflow::Wrap => cell.wrapping_add(T::from_i32(value)),
+
+            Overflow::Error => cell
+                .checked_add_signed(value.into())
+                .ok_or(CellOverflow { index })?,
+        };
+
+        Ok(())
+    }
+
+    /// Sets the current cell to zero.
+    pub fn clear(&mut self) {
+        *self.tape.current_mut() = T::ZERO;
+    }
+
+    /// For each `(offset, factor)` pair, adds the current cell times `factor` to the cell at
+    /// `offset` from the pointer, then sets the current cell to zero. If any target is off the
+    /// tape or would overflow under `Overflow::Error`, no cell is changed.
+    pub fn mul_add(&mut self, targets: &[(isize, i32)]) -> Result<(), MulAddError> {
+        let value = self.tape.get();
+
+        if value == T::ZERO {
+            return Ok(());
+        }
+
+        // Growing the tape to the left shifts every index, so the targets are only resolved for
+        // writing once the tape has room for all of them.
+        for (offset, _) in targets {
+            self.tape
+                .resolve(*offset)
+                .map_err(MulAddError::OutOfBounds)?;
+        }
+
+        if self.options.overflow == Overflow::Error {
+            for (offset, factor) in targets {
+                let index = self
+                    .tape
+                    .resolve(*offset)
+                    .map_err(MulAddError::OutOfBounds)?;
+
+                let product = i64::from(value.to_u32()) * i64::from(*factor);
+
+                if self.tape.cells[index].checked_add_signed(product).is_none() {
+                    return Err(MulAddError::Overflow(CellOverflow { index }));
+                }
+            }
+        }
+
+        for (offset, factor) in targets {
+            let index = self
+                .tape
+                .resolve(*offset)
+                .map_err(MulAddError::OutOfBounds)?;
+
+            let cell = &mut self.tape.cells[index];
+            *cell = cell.wrapping_add(value.wrapping_mul(T::from_i32(*factor)));
+        }
+
+        self.clear();
+
+        Ok(())
+    }
+
+    /// Moves the pointer to the left.
+    pub fn shl(&mut self) -> Result<(), OutOfBounds> {
+        self.move_by(-1)
+    }
+
+    /// Moves the pointer to the right.
+    pub fn shr(&mut self) -> Result<(), OutOfBounds> {
+        self.move_by(1)
+    }
+
+    /// Moves the pointer by an offset. What happens at the ends of the tape depends on the `tape`
+    /// option.
+    pub fn move_by(&mut self, offset: isize) -> Result<(), OutOfBounds> {
+        self.tape.move_by(offset)
+    }
+
+    /// Moves the pointer by a step until it points at a zero cell. Each move adds one to `steps`
+    /// and is checked against the limit first, so a scan over a wrapping tape with no zero cells
+    /// still stops once it runs out of fuel or time.
+    pub fn scan(&mut self, step: isize, limit: Limit, steps: &mut u64) -> Result<(), ScanError> {
+        while self.tape.get() != T::ZERO {
+            if let Some(status) = limit.exceeded(*steps) {
+                return Err(ScanError::Limit(status));
+            }
+
+            *steps += 1;
+            self.move_by(step).map_err(ScanError::OutOfBounds)?;
+        }
+
+        Ok(())
+    }
+
+    /// Repeats the inner code while the current cell is nonzero. If `check_balance` is enabled,
+    /// iterations which move the pointer are recorded as unbalanced loops.
+    pub fn repeat(&mut self, mut f: impl FnMut(&mut Self)) {
+        while self.tape.get() != T::ZERO {
+            let start = self.position();
+
+            f(self);
+
+            if self.options.check_balance && self.position() != start {
+                self.report_unbalanced(UnbalancedLoop {
+                    location: None,
+                    start,
+                    end: self.position(),
+                });
+            }
+        }
+    }
+
+    /// Gets the cells of the tape.
+    pub(crate) fn cells(&self) -> &[T] {
+        &self.tape.cells
+    }
+
+    /// Gets a mutable ref to the input stream of this runner.
+    pub(crate) fn input_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Gets the cells of the tape along with the index of the current cell, for engines which run
+    /// programs without going through the runner's commands.
+    pub(crate) fn tape_mut(&mut self) -> (&mut [T], &mut usize) {
+        (&mut self.tape.cells, &mut self.tape.index)
+    }
+
+    /// Gets a mutable ref to the input queued ahead of the input stream.
+    pub(crate) fn queued_mut(&mut self) -> &mut VecDeque<u8> {
+        &mut self.queued
+    }
+
+    /// Gets a mutable ref to the output stream of this runner.
+    pub(crate) fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    /// Gets a mutable ref to the snapshots of the tape recorded so far.
+    pub(crate) fn dumps_mut(&mut self) -> &mut Vec<TapeDump<T>> {
+        &mut self.dumps
+    }
+
+    /// Gets the length of the tape along with the index of the first cell it started with, both of
+    /// which change as the tape grows.
+    pub(crate) fn extent(&self) -> (usize, usize) {
+        (self.tape.cells.len(), self.tape.origin)
+    }
+
+    /// Shrinks the tape back to an earlier length and origin, removing the cells it has grown by
+    /// since then.
+    pub(crate) fn shrink(&mut self, len: usize, origin: usize) {
+        let extra = self.tape.origin - origin;
+
+        self.tape.cells.drain(..extra);
+        self.tape.cells.truncate(len);
+        self.tape.index = self.tape.index.saturating_sub(extra).min(len - 1);
+        self.tape.origin = origin;
+    }
+
+    /// Consumes the runner, outputting its inner contents.
+    pub fn into_inner(self) -> (Vec<T>, usize, I, O) {
+        (self.tape.cells, self.tape.index, self.input, self.output)
+    }
+}
+
+impl<const N: usize, T: CellValue, I: Read, O: Write> Runner<N, T, I, O> {
+    /// Reads a byte from input into the current cell, widening it if cells are wider than a byte.
+    /// Queued input is read first. Once input is exhausted, the cell is changed according to the
+    /// `eof` option.
+    ///
+    /// Output is flushed before reading from the input stream, since an interactive program may
+    /// be waiting for a reply to what it has written so far.
+    pub fn read(&mut self) -> io::Result<()> {
+        let value = self.next_byte()?;
+        let current = self.tape.current_mut();
+        *current = self.options.eof.apply(value, *current);
+
+        Ok(())
+    }
+
+    /// Writes the value of the current cell into output, truncating it to its lowest byte if cells
+    /// are wider than a byte. Output is only flushed if the `flush_writes` option is enabled, so
+    /// buffered streams otherwise only make a system call once their buffer fills up, the program
+    /// reads input, or the program stops.
+    pub fn write(&mut self) -> io::Result<()> {
+        self.write_byte(self.tape.get().to_u8())
+    }
+
+    /// Writes a byte into output, flushing it if the `flush_writes` option is enabled.
+    pub(crate) fn write_byte(&mut self, value: u8) -> io::Result<()> {
+        self.output.write_all(&[value])?;
+
+        if self.options.flush_writes {
+            self.output.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes the next byte of input, or `None` once input is exhausted. Queued input comes first,
+    /// and the output stream is flushed before blocking on the input stream.
+    pub(crate) fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(value) = self.queued.pop_front() {
+            return Ok(Some(value));
+        }
+
+        self.output.flush()?;
+
+        let mut buffer = [0];
+
+        loop {
+            match self.input.read(&mut buffer) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buffer[0])),
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Flushes the output stream, which engines do once a program stops.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+struct RunnerData<'a, T>(&'a [T], usize);
+
+impl<T: CellValue> Debug for RunnerData<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let mut output = if i == self.1 {
+                        "<".to_string()
+                    } else {
+                        String::new()
+                    };
+
+                    output += &format!("{:0width$X}", v, width = 2 * size_of::<T>());
+
+                    if i == self.1 {
+                        output.push('>');
+                    }
+
+                    output
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim()
+        )
+    }
+}
+
+impl<const N: usize, T: CellValue, I: Debug, O: Debug> Debug for Runner<N, T, I, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const DEBUG_DATA_WIDTH: i32 = 8;
+
+        f.debug_struct("Runner")
+            .field(
+                "data",
+                &RunnerData(
+                    &self.tape.cells[0i32.max(self.tape.index as i32 - DEBUG_DATA_WIDTH) as usize
+                        ..(self.tape.cells.len() as i32)
+                            .min(self.tape.index as i32 + DEBUG_DATA_WIDTH)
+                            as usize],
+                    self.tape.index - 0i32.max(self.tape.index as i32 - DEBUG_DATA_WIDTH) as usize,
+                ),
+            )
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tape::TapePolicy, Runner, RunnerOptions, UnbalancedLoop};
+
+    #[test]
+    fn mul_add_off_the_tape_changes_nothing() {
+        let options = RunnerOptions {
+            tape: TapePolicy::Error,
+            ..RunnerOptions::default()
+        };
+
+        let mut runner = Runner::<4>::with_options(b"", options);
+        runner.add(3).unwrap();
+
+        assert!(runner.mul_add(&[(1, 2), (-1, 1)]).is_err());
+        assert_eq!(runner.cells(), [3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn repeat_reports_every_unbalanced_iteration() {
+        let options = RunnerOptions {
+            check_balance: true,
+            ..RunnerOptions::default()
+        };
+
+        // Without a location, iterations of the same loop can't be told apart from other loops.
+        let mut runner = Runner::<4>::with_options(b"", options);
+        runner.add(1).unwrap();
+        runner.move_by(1).unwrap();
+        runner.add(1).unwrap();
+        runner.move_by(-1).unwrap();
+        runner.repeat(|runner| {
+            runner.clear();
+            runner.move_by(1).unwrap();
+        });
+
+        let iteration = |start| UnbalancedLoop {
+            location: None,
+            start,
+            end: start + 1,
+        };
+
+        assert_eq!(runner.position(), 2);
+        assert_eq!(runner.unbalanced_loops(), [iteration(0), iteration(1)]);
+    }
+}
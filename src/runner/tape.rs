@@ -0,0 +1,106 @@
+//! Defines the tape of cells a runner operates on, and what happens at its boundaries.
+
+use super::cell::CellValue;
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+/// What happens when the pointer moves past either end of the tape.
+pub enum TapePolicy {
+    #[default]
+    /// The pointer wraps around to the other end of the tape.
+    Wrap,
+
+    /// Moving past either end is an error, which stops the program. Runs of `<` and `>` are folded
+    /// together when parsing, so only the cell each run ends up at has to be on the tape, and `<>`
+    /// on the first cell never fails.
+    Error,
+
+    /// The tape grows to the right on demand, so its initial size is only a starting capacity.
+    /// Moving left of the first cell is an error.
+    Grow,
+
+    /// The tape grows on demand in both directions. Growing to the left shifts the indices of
+    /// every existing cell to the right.
+    GrowBoth,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/// An error returned when the pointer moves past the end of a tape which doesn't allow it.
+pub struct OutOfBounds {
+    /// The index the pointer tried to move to. This is either negative or at least the length of
+    /// the tape.
+    pub index: isize,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// A tape of cells with a pointer into it.
+pub(super) struct Tape<T> {
+    pub(super) cells: Vec<T>,
+    pub(super) index: usize,
+
+    /// The index of the cell which was first on the tape before it grew to the left.
+    pub(super) origin: usize,
+
+    policy: TapePolicy,
+}
+
+impl<T: CellValue> Tape<T> {
+    /// Constructs a new tape of zeroed cells.
+    pub(super) fn new(size: usize, policy: TapePolicy) -> Self {
+        Self {
+            cells: vec![T::ZERO; size],
+            index: 0,
+            origin: 0,
+            policy,
+        }
+    }
+
+    /// Gets the index of the cell at an offset from the pointer, growing the tape if the policy
+    /// allows it. Growing to the left moves the pointer along with the cell it points at.
+    pub(super) fn resolve(&mut self, offset: isize) -> Result<usize, OutOfBounds> {
+        let len = self.cells.len() as isize;
+        let index = self.index as isize + offset;
+
+        if (0..len).contains(&index) {
+            return Ok(index as usize);
+        }
+
+        match self.policy {
+            TapePolicy::Wrap => Ok(index.rem_euclid(len) as usize),
+
+            TapePolicy::Error => Err(OutOfBounds { index }),
+
+            TapePolicy::Grow | TapePolicy::GrowBoth if index >= len => {
+                // Doubling keeps repeated single steps off the end amortized constant time.
+                let new_len = (index + 1).max(len * 2) as usize;
+                self.cells.resize(new_len, T::ZERO);
+                Ok(index as usize)
+            }
+
+            TapePolicy::Grow => Err(OutOfBounds { index }),
+
+            TapePolicy::GrowBoth => {
+                let extra = (-index).max(len) as usize;
+                self.cells.splice(0..0, std::iter::repeat_n(T::ZERO, extra));
+                self.index += extra;
+                self.origin += extra;
+                Ok((index + extra as isize) as usize)
+            }
+        }
+    }
+
+    /// Moves the pointer by an offset according to the tape's policy.
+    pub(super) fn move_by(&mut self, offset: isize) -> Result<(), OutOfBounds> {
+        self.index = self.resolve(offset)?;
+        Ok(())
+    }
+
+    /// Gets the value of the current cell.
+    pub(super) fn get(&self) -> T {
+        self.cells[self.index]
+    }
+
+    /// Gets a mutable reference to the current cell.
+    pub(super) fn current_mut(&mut self) -> &mut T {
+        &mut self.cells[self.index]
+    }
+}